@@ -139,8 +139,8 @@ impl NamingEventListener for ServiceInstanceEventListener {
         info!("naming event: {:?}", event);
         if let Some(instances) = event.instances.as_deref() {
             let mut alive_keys = HashSet::new();
-            instances.into_iter().for_each(|instance| {
-                let key = hash(&instance);
+            instances.iter().for_each(|instance| {
+                let key = hash(instance);
                 if !self.keys.contains(&key) {
                     self.keys.insert(key);
                     self.tx
@@ -24,6 +24,7 @@ pub trait Discovery: sealed::Sealed {
     ///
     /// poll element change event(s)
     ///
+    #[allow(clippy::type_complexity)]
     fn poll_change(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -60,6 +61,7 @@ where
 
     type Error = E;
 
+    #[allow(clippy::type_complexity)]
     fn poll_change(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
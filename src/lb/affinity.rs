@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+///
+/// Pin the request to the element which matches the predicate, put it in the request extensions.
+/// If no supplied element matches, the load balancer will fall back to the policy.
+///
+pub struct Affinity<I> {
+    predicate: Arc<dyn Fn(&I) -> bool + Send + Sync>,
+
+    ///
+    /// the position of the element matched last time, it's checked before the others
+    ///
+    hint: Arc<AtomicUsize>,
+}
+
+impl<I> Clone for Affinity<I> {
+    fn clone(&self) -> Self {
+        Self {
+            predicate: self.predicate.clone(),
+            hint: self.hint.clone(),
+        }
+    }
+}
+
+impl<I> Affinity<I> {
+    pub fn new<F: Fn(&I) -> bool + Send + Sync + 'static>(predicate: F) -> Self {
+        Self::with_hint(predicate, Arc::default())
+    }
+
+    ///
+    /// The affinity shares the position hint, so the repeated pins to the same element check the
+    /// position matched last time first
+    ///
+    pub(crate) fn with_hint<F>(predicate: F, hint: Arc<AtomicUsize>) -> Self
+    where
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            predicate: Arc::new(predicate),
+            hint,
+        }
+    }
+
+    pub fn matches(&self, item: &I) -> bool {
+        (self.predicate)(item)
+    }
+
+    ///
+    /// The position of the element matches the predicate, the hint is checked first
+    ///
+    pub(crate) fn position(&self, items: &[I]) -> Option<usize> {
+        let hint = self.hint.load(Ordering::Relaxed);
+        if items.get(hint).is_some_and(|item| self.matches(item)) {
+            return Some(hint);
+        }
+        let position = items.iter().position(|item| self.matches(item))?;
+        self.hint.store(position, Ordering::Relaxed);
+        Some(position)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::lb::hash::{hash, Version};
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::tracker::InFlight;
+use crate::lb::weight::choose_weighted;
+use crate::lb::{OutOfRange, Route, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+///
+/// The default min aperture size
+///
+const DEFAULT_MIN_APERTURE: usize = 12;
+
+///
+/// The default load band, the average in flight requests of the elements in the window
+///
+const DEFAULT_LOAD_BAND: (f64, f64) = (0.5, 2.0);
+
+///
+/// The resolution of the overlap weights
+///
+const RESOLUTION: f64 = 1_000_000.0;
+
+///
+/// The deterministic aperture, each client talks to a small window of the elements instead of
+/// all of them. The clients and the elements are placed on the same ring, the client `index` of
+/// `count` owns the range `[index / count, (index + 1) / count)`, and its window starts from
+/// the range with the aperture size, so the windows of the clients spread evenly over the
+/// elements. The elements are ordered by their hash, so all the clients see the same ring.
+///
+/// The window widens when the average in flight requests of the elements in the window is above
+/// the load band, and narrows when it's below, but never less than the min aperture.
+///
+/// ```
+/// use reqwest_lb::{Aperture, LoadBalancerPolicy};
+///
+/// // the third of 100 client instances
+/// let aperture = Aperture::new(2, 100).min_aperture(6).load_band(1.0, 4.0);
+/// let policy = LoadBalancerPolicy::<u16>::aperture(aperture);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Aperture {
+    index: usize,
+    count: usize,
+    min_aperture: usize,
+    load_band: (f64, f64),
+}
+
+impl Aperture {
+    pub fn new(index: usize, count: usize) -> Self {
+        let count = count.max(1);
+        Self {
+            index: index % count,
+            count,
+            min_aperture: DEFAULT_MIN_APERTURE,
+            load_band: DEFAULT_LOAD_BAND,
+        }
+    }
+
+    ///
+    /// Set the min aperture size, default is `12`
+    ///
+    pub fn min_aperture(mut self, min_aperture: usize) -> Self {
+        self.min_aperture = min_aperture.max(1);
+        self
+    }
+
+    ///
+    /// Set the load band (low, high) of the average in flight requests, default is `(0.5, 2.0)`
+    ///
+    pub fn load_band(mut self, low: f64, high: f64) -> Self {
+        self.load_band = (low.min(high), high.max(low));
+        self
+    }
+}
+
+pub(crate) struct DeterministicAperture<I> {
+    aperture: Aperture,
+    size: AtomicUsize,
+    in_flight: InFlight<I>,
+    ring: Mutex<(Version, Vec<usize>)>,
+}
+
+impl<I> DeterministicAperture<I> {
+    pub fn new(aperture: Aperture) -> Self {
+        Self {
+            size: AtomicUsize::new(aperture.min_aperture),
+            aperture,
+            in_flight: InFlight::default(),
+            ring: Mutex::new((Version::default(), Vec::new())),
+        }
+    }
+
+    ///
+    /// The positions on the ring in the window with their weights, the weight is the overlap of
+    /// the element range and the window
+    ///
+    fn window(&self, len: usize) -> Vec<(usize, f64)> {
+        let unit = len as f64 / self.aperture.count as f64;
+        let size = self.size.load(Ordering::Relaxed);
+        // the window covers the range of the client at least
+        let width = (size as f64).max(unit).min(len as f64);
+        let start = self.aperture.index as f64 * unit;
+        let end = start + width;
+
+        let mut weights = Vec::new();
+        let mut position = start.floor() as usize;
+        while (position as f64) < end {
+            let overlap = end.min((position + 1) as f64) - start.max(position as f64);
+            if overlap > 0.0 {
+                weights.push((position % len, overlap));
+            }
+            position += 1;
+        }
+        // the window wrapped around to its first element
+        if weights.len() > 1 && weights[0].0 == weights[weights.len() - 1].0 {
+            let (_, overlap) = weights.pop().unwrap();
+            weights[0].1 += overlap;
+        }
+        weights
+    }
+}
+
+impl<I> sealed::Sealed<I> for DeterministicAperture<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for DeterministicAperture<I>
+where
+    I: Hash + Eq + Clone + Send + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        _: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let candidates = {
+            let mut ring = self.ring.lock().unwrap();
+            let (version, ring) = &mut *ring;
+            // place the elements on the ring in the order of the hash, only when they changed
+            if version.update(items, statistic) {
+                *ring = (0..items.len()).collect();
+                ring.sort_by_key(|index| (hash(&items[*index]), *index));
+            }
+            self.window(items.len())
+                .into_iter()
+                .map(|(position, weight)| {
+                    let index = ring[position];
+                    (index, weight, self.in_flight.get(&items[index]))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // widen or narrow the window by the load of the current window
+        let load = candidates.iter().map(|(_, _, load)| *load).sum::<usize>() as f64
+            / candidates.len().max(1) as f64;
+        let size = self.size.load(Ordering::Relaxed);
+        let (low, high) = self.aperture.load_band;
+        if load > high && size < items.len() {
+            self.size.store(size + 1, Ordering::Relaxed);
+        } else if load < low && size > self.aperture.min_aperture {
+            self.size.store(size - 1, Ordering::Relaxed);
+        }
+
+        // power of two choices in proportion to the overlap weights
+        let pick = || {
+            statistic.with_rng(|rng| {
+                choose_weighted(
+                    candidates
+                        .iter()
+                        .map(|(_, weight, _)| (weight * RESOLUTION) as usize),
+                    rng,
+                )
+            })
+        };
+        let (Some(a), Some(b)) = (pick(), pick()) else {
+            return Ok(None);
+        };
+        let cost = |(_, weight, load): &(usize, f64, usize)| (*load + 1) as f64 / weight;
+        let chosen = if cost(&candidates[b]) < cost(&candidates[a]) {
+            b
+        } else {
+            a
+        };
+        Ok(Some(candidates[chosen].0))
+    }
+
+    fn start(&self, item: &I, _: &Route) {
+        self.in_flight.increment(item);
+    }
+
+    fn finish(&self, item: &I, _: Duration, _: &Route) {
+        self.in_flight.decrement(item);
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(DeterministicAperture::new(self.aperture.clone())))
+    }
+}
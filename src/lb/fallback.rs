@@ -0,0 +1,85 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicy, LoadBalancerPolicyTrait};
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+///
+/// The id of the next fallback, to mark its choice in the route
+///
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+///
+/// Try the policies in order, fall through to the next one when the policy declines, the hooks
+/// of a request are sent to the policy which chose the element only, by the mark in the
+/// [`Route`] of the request
+///
+pub(crate) struct Fallback<I> {
+    id: u64,
+    policies: Vec<LoadBalancerPolicy<I>>,
+}
+
+impl<I> Fallback<I> {
+    pub fn new(policies: Vec<LoadBalancerPolicy<I>>) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            policies,
+        }
+    }
+
+    fn policy(&self, route: &Route) -> Option<&LoadBalancerPolicy<I>> {
+        self.policies.get(route.get(self.id)?)
+    }
+}
+
+impl<I> sealed::Sealed<I> for Fallback<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for Fallback<I>
+where
+    I: 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        // the index out of the elements is an error, don't fall through to the next policy
+        for (policy, f) in self.policies.iter().enumerate() {
+            if let Some(index) =
+                OutOfRange::check(f.choose(items, statistic, extensions)?, items.len())?
+            {
+                if let Some(route) = extensions.get::<Route>() {
+                    route.mark(self.id, policy);
+                }
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn start(&self, item: &I, route: &Route) {
+        if let Some(policy) = self.policy(route) {
+            policy.start(item, route)
+        }
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        if let Some(policy) = self.policy(route) {
+            policy.finish(item, elapsed, route)
+        }
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        if let Some(policy) = self.policy(route) {
+            policy.record(item, outcome, route)
+        }
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Fallback::new(
+            self.policies.iter().map(|policy| policy.fresh()).collect(),
+        )))
+    }
+}
@@ -0,0 +1,157 @@
+use crate::LoadBalancerTrait;
+use http::{Extensions, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///
+/// The outcome of a request to the chosen element
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    ///
+    /// the request succeeded
+    ///
+    pub success: bool,
+
+    ///
+    /// the response status, `None` if the request failed without response
+    ///
+    pub status: Option<StatusCode>,
+
+    ///
+    /// the latency of the request
+    ///
+    pub elapsed: Duration,
+
+    ///
+    /// the load reported by the element in thousandths (e.g. `700` is `0.7`), `None` if it's
+    /// not reported
+    ///
+    pub load: Option<u32>,
+}
+
+///
+/// The route of a request, the load balancer puts a new one in the extensions at each choose,
+/// and the composite policies (e.g. the fallback) mark the nested policy which chose the element
+/// in it, so the hooks of the request go along the route rather than guessed by the element.
+/// It's carried by the [`Feedback`], the clones share the marks.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Route(Arc<Mutex<HashMap<u64, usize>>>);
+
+impl Route {
+    ///
+    /// Mark the nested policy chose the element in the composite policy of the id
+    ///
+    pub(crate) fn mark(&self, id: u64, policy: usize) {
+        self.0.lock().unwrap().insert(id, policy);
+    }
+
+    ///
+    /// The nested policy chose the element in the composite policy of the id
+    ///
+    pub(crate) fn get(&self, id: u64) -> Option<usize> {
+        self.0.lock().unwrap().get(&id).copied()
+    }
+}
+
+///
+/// The resolution of the reported load
+///
+pub(crate) const LOAD_RESOLUTION: f64 = 1_000.0;
+
+///
+/// The handle of a request to the chosen element, the load balancer will be notified the
+/// request started when created, and finished when dropped. The outcome can be reported by
+/// [`Feedback::record_success`] or [`Feedback::record_failure`] before the request finished.
+///
+/// It takes the [`Route`] of the choose from the extensions, so create it before the next choose
+/// with the same extensions.
+///
+/// ```rust
+/// use http::{Extensions, StatusCode};
+/// use reqwest_lb::supplier::LoadBalancer;
+/// use reqwest_lb::{Feedback, LoadBalancerPolicy, LoadBalancerTrait};
+///
+/// # async fn run() {
+/// let load_balancer = LoadBalancer::new(vec![1, 2, 3], LoadBalancerPolicy::least_requests());
+/// let mut extensions = Extensions::new();
+/// let element = load_balancer.choose(&mut extensions).await.unwrap().unwrap();
+/// let feedback = Feedback::new(&load_balancer, element, &extensions);
+/// // send the request to the element
+/// feedback.record_success(StatusCode::OK);
+/// # }
+/// ```
+///
+pub struct Feedback<'a, L: LoadBalancerTrait + ?Sized> {
+    load_balancer: &'a L,
+    element: L::Element,
+    route: Route,
+    started: Instant,
+    load: Option<u32>,
+}
+
+impl<'a, L: LoadBalancerTrait + ?Sized> Feedback<'a, L> {
+    pub fn new(load_balancer: &'a L, element: L::Element, extensions: &Extensions) -> Self {
+        let route = extensions.get::<Route>().cloned().unwrap_or_default();
+        load_balancer.start(&element, &route);
+        Self {
+            load_balancer,
+            element,
+            route,
+            started: Instant::now(),
+            load: None,
+        }
+    }
+
+    ///
+    /// The chosen element
+    ///
+    pub fn element(&self) -> &L::Element {
+        &self.element
+    }
+
+    ///
+    /// Report the load of the element, e.g. the utilization in the response header, it will be
+    /// recorded with the outcome in thousandths, the negative or NaN load is ignored
+    ///
+    pub fn report_load(&mut self, load: f64) {
+        if load >= 0.0 {
+            // the cast saturates the overflowed load
+            self.load = Some((load * LOAD_RESOLUTION).round() as u32);
+        }
+    }
+
+    ///
+    /// Report the request succeeded with the response status
+    ///
+    pub fn record_success(self, status: StatusCode) {
+        self.record(true, Some(status))
+    }
+
+    ///
+    /// Report the request failed, with the response status if it's responded
+    ///
+    pub fn record_failure(self, status: Option<StatusCode>) {
+        self.record(false, status)
+    }
+
+    fn record(self, success: bool, status: Option<StatusCode>) {
+        let outcome = Outcome {
+            success,
+            status,
+            elapsed: self.started.elapsed(),
+            load: self.load,
+        };
+        self.load_balancer
+            .record(&self.element, &outcome, &self.route);
+    }
+}
+
+impl<L: LoadBalancerTrait + ?Sized> Drop for Feedback<'_, L> {
+    fn drop(&mut self) {
+        self.load_balancer
+            .finish(&self.element, self.started.elapsed(), &self.route);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::lb::Statistic;
+use std::hash::{Hash, Hasher};
+
+///
+/// The request key for the hash policies, put it in the request extensions
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HashKey(pub u64);
+
+impl HashKey {
+    pub fn new<T: Hash + ?Sized>(key: &T) -> Self {
+        Self(hash(key))
+    }
+}
+
+///
+/// The FNV-1a hasher with the murmur3 finalizer, the output is stable across the processes, the
+/// platforms and the rust versions (unlike the std `DefaultHasher`), so the peers agree on the
+/// ring placement and the order of the elements.
+///
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        let mut h = self.0;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^ (h >> 33)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // the integers are written in little endian and the usize as u64, so the platforms agree
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64)
+    }
+}
+
+pub(crate) fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = StableHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+///
+/// The fingerprint of the elements, used to check the elements whether changed
+///
+pub(crate) fn fingerprint<I: Hash>(items: &[I]) -> u64 {
+    hash(items)
+}
+
+///
+/// The version of the elements, the generation of the supplier is checked first, the fingerprint
+/// is checked only when the generation is unknown or changed
+///
+#[derive(Default)]
+pub(crate) struct Version {
+    generation: Option<u64>,
+    len: usize,
+    fingerprint: u64,
+}
+
+impl Version {
+    ///
+    /// Update the version to the elements, return `true` if the elements changed
+    ///
+    pub fn update<I: Hash>(&mut self, items: &[I], statistic: &Statistic) -> bool {
+        if statistic.generation.is_some()
+            && statistic.generation == self.generation
+            && items.len() == self.len
+        {
+            return false;
+        }
+        let fingerprint = fingerprint(items);
+        let changed = items.len() != self.len || fingerprint != self.fingerprint;
+        *self = Version {
+            generation: statistic.generation,
+            len: items.len(),
+            fingerprint,
+        };
+        changed
+    }
+}
@@ -0,0 +1,89 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::tracker::InFlight;
+use crate::lb::weight::{weights, WeightProvider};
+use crate::lb::{OutOfRange, Route, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+///
+/// Choose the element with the fewest in flight requests, or the lowest ratio of the in flight
+/// requests to the weight if the weight is provided, the ties will be broken randomly
+///
+pub(crate) struct LeastRequests<I> {
+    in_flight: InFlight<I>,
+    weight: Option<Arc<dyn WeightProvider<I> + Send + Sync>>,
+}
+
+impl<I> LeastRequests<I> {
+    pub fn new() -> Self {
+        Self {
+            in_flight: InFlight::default(),
+            weight: None,
+        }
+    }
+
+    pub fn weighted(weight: Arc<dyn WeightProvider<I> + Send + Sync>) -> Self {
+        Self {
+            in_flight: InFlight::default(),
+            weight: Some(weight),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for LeastRequests<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for LeastRequests<I>
+where
+    I: Hash + Eq + Clone + Send + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        // the load is the fraction (in flight, weight), the zero weight will never be chosen
+        let weights = match &self.weight {
+            Some(f) => weights(&**f, items),
+            None => vec![1; items.len()],
+        };
+        let weights = Scale::weights(extensions, items, weights);
+        let loads = items
+            .iter()
+            .zip(weights)
+            .map(|(item, weight)| (self.in_flight.get(item) as u128, weight as u128))
+            .collect::<Vec<_>>();
+        let Some(min) = loads
+            .iter()
+            .filter(|(_, weight)| *weight > 0)
+            .min_by(|a, b| (a.0 * b.1).cmp(&(b.0 * a.1)))
+        else {
+            return Ok(None);
+        };
+        let indexes = loads
+            .iter()
+            .enumerate()
+            .filter(|(_, load)| load.1 > 0 && load.0 * min.1 == min.0 * load.1)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        Ok(Some(indexes[statistic.random(indexes.len())]))
+    }
+
+    fn start(&self, item: &I, _: &Route) {
+        self.in_flight.increment(item);
+    }
+
+    fn finish(&self, item: &I, _: Duration, _: &Route) {
+        self.in_flight.decrement(item);
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(LeastRequests {
+            in_flight: InFlight::default(),
+            weight: self.weight.clone(),
+        }))
+    }
+}
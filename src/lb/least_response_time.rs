@@ -0,0 +1,185 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///
+/// The max samples count of each element in the window
+///
+const MAX_SAMPLES: usize = 1024;
+
+///
+/// The default percentile if it's not a number
+///
+const DEFAULT_PERCENTILE: f64 = 0.5;
+
+#[derive(Default)]
+struct Window {
+    samples: VecDeque<(Instant, Duration)>,
+    // the latencies of the samples in order, so the percentile is a lookup
+    sorted: Vec<Duration>,
+    // the failed requests not finished, their latencies are sampled as the penalty already
+    failed: usize,
+}
+
+impl Window {
+    fn push(&mut self, now: Instant, latency: Duration) {
+        self.samples.push_back((now, latency));
+        let position = self.sorted.partition_point(|d| *d < latency);
+        self.sorted.insert(position, latency);
+    }
+
+    fn evict(&mut self, now: Instant, window: Duration) {
+        while let Some((stamp, latency)) = self.samples.front() {
+            if now.saturating_duration_since(*stamp) > window || self.samples.len() > MAX_SAMPLES {
+                let position = self.sorted.partition_point(|d| d < latency);
+                self.sorted.remove(position);
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.sorted.is_empty() {
+            return None;
+        }
+        let rank = (percentile * (self.sorted.len() - 1) as f64).round() as usize;
+        Some(self.sorted[rank.min(self.sorted.len() - 1)])
+    }
+}
+
+///
+/// Choose the element with the lowest latency percentile in the sliding window, the element
+/// without samples (new or expired) is taken as the mean of the others, and a random element
+/// will be chosen at the exploration rate, so the samples of the slower elements keep fresh. The
+/// failed request is sampled as the latency of the whole window rather than its own, so the
+/// element fails fast is not taken as the fastest.
+///
+pub(crate) struct LeastResponseTime<I> {
+    percentile: f64,
+    window: Duration,
+    exploration: f64,
+    windows: Mutex<HashMap<I, Window>>,
+}
+
+impl<I> LeastResponseTime<I> {
+    pub fn new(percentile: f64, window: Duration, exploration: f64) -> Self {
+        // the rates are clamped, and the one not a number falls back to the default
+        let percentile = if percentile.is_nan() {
+            DEFAULT_PERCENTILE
+        } else {
+            percentile.clamp(0.0, 1.0)
+        };
+        let exploration = if exploration.is_nan() {
+            0.0
+        } else {
+            exploration.clamp(0.0, 1.0)
+        };
+        Self {
+            percentile,
+            window,
+            exploration,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for LeastResponseTime<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for LeastResponseTime<I>
+where
+    I: Hash + Eq + Clone + Send + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        if statistic.random_bool(self.exploration) {
+            return Ok(Some(statistic.random(items.len())));
+        }
+
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        // drop the state of the removed elements
+        if windows.len() > items.len() {
+            windows.retain(|item, _| items.contains(item));
+        }
+
+        let latencies = items
+            .iter()
+            .map(|item| {
+                let window = windows.get_mut(item)?;
+                window.evict(now, self.window);
+                window.percentile(self.percentile)
+            })
+            .collect::<Vec<_>>();
+
+        // the unknown latency is the mean of the known ones, neither flooded nor starved
+        let known = latencies.iter().flatten().collect::<Vec<_>>();
+        let mean = match known.len() {
+            0 => Duration::ZERO,
+            len => known.iter().copied().sum::<Duration>() / len as u32,
+        };
+        let latencies = latencies
+            .into_iter()
+            .zip(items)
+            .map(|(latency, item)| {
+                let factor = Scale::factor(extensions, item).max(f64::MIN_POSITIVE);
+                latency.unwrap_or(mean).as_secs_f64() / factor
+            })
+            .collect::<Vec<_>>();
+        let min = latencies
+            .iter()
+            .copied()
+            .min_by(f64::total_cmp)
+            .unwrap_or_default();
+        let indexes = latencies
+            .iter()
+            .enumerate()
+            .filter(|(_, latency)| **latency == min)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        Ok(Some(indexes[statistic.random(indexes.len())]))
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, _: &Route) {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(item.clone()).or_default();
+        if window.failed > 0 {
+            window.failed -= 1;
+        } else {
+            window.push(now, elapsed);
+        }
+        window.evict(now, self.window);
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, _: &Route) {
+        if outcome.success {
+            return;
+        }
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(item.clone()).or_default();
+        window.push(now, outcome.elapsed.max(self.window));
+        window.failed += 1;
+        window.evict(now, self.window);
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(LeastResponseTime::new(
+            self.percentile,
+            self.window,
+            self.exploration,
+        )))
+    }
+}
@@ -0,0 +1,181 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicy, LoadBalancerPolicyTrait, Subsets};
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::sync::Arc;
+use std::time::Duration;
+
+///
+/// The default capacity threshold of the local zone
+///
+const DEFAULT_THRESHOLD: f64 = 0.8;
+
+///
+/// Keep the traffic in the local zone while the local zone has enough capacity, the capacity is
+/// the healthy share of the local elements. Once the capacity falls below the threshold, the
+/// traffic spills to the other zones in proportion to their healthy elements, e.g. the capacity
+/// is 40% and the threshold is 80%, then the half of the traffic will be spilled. The local and
+/// the other zones have their own state of the policy.
+///
+/// If there is no healthy element in all zones, all the elements will be treated as healthy.
+///
+/// ```
+/// use reqwest_lb::{LoadBalancerPolicy, Locality};
+///
+/// #[derive(Clone)]
+/// struct Node {
+///     zone: String,
+///     port: u16,
+/// }
+///
+/// let locality = Locality::new(LoadBalancerPolicy::RoundRobin, "us-east-1a", |node: &Node| {
+///     node.zone.as_str()
+/// })
+/// .threshold(0.5);
+/// let policy = LoadBalancerPolicy::locality(locality);
+/// ```
+///
+pub struct Locality<I> {
+    zone: Arc<str>,
+    accessor: Arc<dyn Fn(&I) -> &str + Send + Sync>,
+    healthy: Arc<dyn Fn(&I) -> bool + Send + Sync>,
+    threshold: f64,
+    subsets: Arc<Subsets<bool, I>>,
+}
+
+impl<I> Clone for Locality<I> {
+    fn clone(&self) -> Self {
+        Self {
+            zone: self.zone.clone(),
+            accessor: self.accessor.clone(),
+            healthy: self.healthy.clone(),
+            threshold: self.threshold,
+            subsets: self.subsets.clone(),
+        }
+    }
+}
+
+impl<I: Clone + 'static> Locality<I> {
+    pub fn new<F>(policy: LoadBalancerPolicy<I>, zone: &str, accessor: F) -> Self
+    where
+        F: Fn(&I) -> &str + Send + Sync + 'static,
+    {
+        Self {
+            zone: Arc::from(zone),
+            accessor: Arc::new(accessor),
+            healthy: Arc::new(|_| true),
+            threshold: DEFAULT_THRESHOLD,
+            subsets: Arc::new(Subsets::new(policy)),
+        }
+    }
+
+    ///
+    /// Set the element health check, all the elements are healthy by default
+    ///
+    pub fn healthy<F>(mut self, healthy: F) -> Self
+    where
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        self.healthy = Arc::new(healthy);
+        self
+    }
+
+    ///
+    /// Set the capacity threshold of the local zone, default is `0.8`
+    ///
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    fn is_local(&self, item: &I) -> bool {
+        (self.accessor)(item) == &*self.zone
+    }
+}
+
+impl<I> sealed::Sealed<I> for Locality<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for Locality<I>
+where
+    I: Clone + Send + Sync + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let (mut local_all, mut remote_all) = (Vec::new(), Vec::new());
+        let (mut local_healthy, mut remote_healthy) = (Vec::new(), Vec::new());
+        for (index, item) in items.iter().enumerate() {
+            let is_local = self.is_local(item);
+            let (all, healthy) = if is_local {
+                (&mut local_all, &mut local_healthy)
+            } else {
+                (&mut remote_all, &mut remote_healthy)
+            };
+            all.push(index);
+            if (self.healthy)(item) {
+                healthy.push(index);
+            }
+        }
+
+        // panic mode: no healthy element at all, spread the load to all the elements
+        if local_healthy.is_empty() && remote_healthy.is_empty() {
+            let share = local_all.len() as f64 / items.len().max(1) as f64;
+            let (is_local, indexes) = if statistic.random_bool(share) {
+                (true, &local_all)
+            } else {
+                (false, &remote_all)
+            };
+            return self
+                .subsets
+                .choose(is_local, items, indexes, statistic, extensions);
+        }
+
+        let local = local_all.len();
+        let capacity = if local == 0 {
+            0.0
+        } else {
+            local_healthy.len() as f64 / local as f64
+        };
+        let share = if capacity >= self.threshold {
+            1.0
+        } else {
+            capacity / self.threshold
+        };
+        let (is_local, indexes) = if remote_healthy.is_empty()
+            || (!local_healthy.is_empty() && statistic.random_bool(share))
+        {
+            (true, &local_healthy)
+        } else {
+            (false, &remote_healthy)
+        };
+        self.subsets
+            .choose(is_local, items, indexes, statistic, extensions)
+    }
+
+    fn start(&self, item: &I, route: &Route) {
+        if let Some(policy) = self.subsets.get(&self.is_local(item)) {
+            policy.start(item, route)
+        }
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        if let Some(policy) = self.subsets.get(&self.is_local(item)) {
+            policy.finish(item, elapsed, route)
+        }
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        if let Some(policy) = self.subsets.get(&self.is_local(item)) {
+            policy.record(item, outcome, route)
+        }
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Locality {
+            subsets: Arc::new(Subsets::new(self.subsets.policy().clone())),
+            ..self.clone()
+        }))
+    }
+}
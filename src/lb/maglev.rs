@@ -0,0 +1,110 @@
+use crate::lb::hash::{hash, HashKey, Version};
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::{OutOfRange, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Table {
+    entries: Vec<usize>,
+}
+
+impl Table {
+    fn build<I: Hash>(items: &[I], size: usize) -> Self {
+        let size = next_prime(size.max(items.len()));
+        let permutations = items
+            .iter()
+            .map(|item| {
+                let offset = (hash(&(item, 0)) % size as u64) as usize;
+                let skip = (hash(&(item, 1)) % (size as u64 - 1) + 1) as usize;
+                (offset, skip)
+            })
+            .collect::<Vec<_>>();
+
+        // each element fills its next preferred empty entry by turns, until the table is full
+        let mut next = vec![0; items.len()];
+        let mut entries = vec![usize::MAX; size];
+        let mut filled = 0;
+        'outer: loop {
+            for (index, (offset, skip)) in permutations.iter().enumerate() {
+                let mut entry = (offset + next[index] * skip) % size;
+                while entries[entry] != usize::MAX {
+                    next[index] += 1;
+                    entry = (offset + next[index] * skip) % size;
+                }
+                entries[entry] = index;
+                next[index] += 1;
+                filled += 1;
+                if filled == size {
+                    break 'outer;
+                }
+            }
+        }
+        Self { entries }
+    }
+}
+
+fn next_prime(n: usize) -> usize {
+    let is_prime = |n: usize| n >= 2 && (2..).take_while(|i| i * i <= n).all(|i| n % i != 0);
+    (n.max(2)..).find(|n| is_prime(*n)).unwrap()
+}
+
+///
+/// Google's Maglev consistent hash, the lookup table will be rebuilt only when the elements changed,
+/// the request will be routed by the [`HashKey`] in the extensions, the request without the key
+/// will be routed to a random element, or declined if `decline` is set.
+///
+pub(crate) struct Maglev {
+    size: usize,
+    decline: bool,
+    table: Mutex<(Version, Table)>,
+}
+
+impl Maglev {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            decline: false,
+            table: Mutex::new((Version::default(), Table::default())),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for Maglev {}
+
+impl<I: Hash> LoadBalancerPolicyTrait<I> for Maglev {
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let Some(key) = extensions.get::<HashKey>() else {
+            return Ok((!self.decline).then(|| statistic.random(items.len())));
+        };
+        let mut table = self.table.lock().unwrap();
+        let (version, table) = &mut *table;
+        // rebuild the table only when the elements changed
+        if version.update(items, statistic) {
+            *table = Table::build(items, self.size);
+        }
+        Ok(Some(
+            table.entries[(key.0 % table.entries.len() as u64) as usize],
+        ))
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Maglev {
+            decline: self.decline,
+            ..Maglev::new(self.size)
+        }))
+    }
+
+    fn decline_without_key(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Maglev {
+            decline: true,
+            ..Maglev::new(self.size)
+        }))
+    }
+}
@@ -1,21 +1,59 @@
-mod registry;
+mod affinity;
+mod aperture;
+mod fallback;
+mod feedback;
+pub(crate) mod hash;
+mod least_requests;
+mod least_response_time;
+mod locality;
+mod maglev;
+mod offset_round_robin;
+mod peak_ewma;
 mod policy;
+mod priority;
+mod registry;
+mod rendezvous;
+mod ring;
+mod scale;
+mod server_load;
+mod slow_start;
+mod smooth_weight;
+mod split;
+mod stateful;
+mod tracker;
 mod weight;
 
 use futures::future::BoxFuture;
 use http::Extensions;
-use std::fmt::Debug;
+use rand::{Rng, RngCore};
+use std::fmt::{Debug, Formatter};
 use std::future::Future;
 use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
 
+pub use affinity::Affinity;
+pub use aperture::Aperture;
+pub use feedback::{Feedback, Outcome, Route};
+pub use hash::HashKey;
+pub use locality::Locality;
+pub use policy::{
+    AsyncLoadBalancerPolicyTrait, CustomPolicy, LoadBalancerPolicy, LoadBalancerPolicyTrait,
+};
+pub use priority::Priority;
 pub use registry::LoadBalancerRegistry;
-pub use policy::{LoadBalancerPolicy, LoadBalancerPolicyTrait};
+pub use slow_start::SlowStart;
+pub use split::{Split, SplitBuilder};
+pub use stateful::StatefulPolicy;
 pub use weight::WeightProvider;
 
 pub type BoxLoadBalancer<I, E> = Box<
-    dyn LoadBalancerTrait<Element = I, Error = E, Future = BoxFuture<'static, Result<Option<I>, E>>>
-        + Send
+    dyn LoadBalancerTrait<
+            Element = I,
+            Error = E,
+            Future = BoxFuture<'static, Result<Option<I>, ChooseError<E>>>,
+        > + Send
         + Sync,
 >;
 
@@ -33,13 +71,29 @@ pub trait LoadBalancerTrait {
     ///
     /// load balancer choose element future type
     ///
-    type Future: Future<Output = Result<Option<Self::Element>, Self::Error>>;
+    type Future: Future<Output = Result<Option<Self::Element>, ChooseError<Self::Error>>>;
 
     ///
     /// load balancer choose a effect element
     ///
     fn choose(&self, extensions: &mut Extensions) -> Self::Future;
 
+    ///
+    /// notify the load balancer a request to the chosen element started, the [`Route`] of the
+    /// request is put in the extensions by the choose
+    ///
+    fn start(&self, _element: &Self::Element, _route: &Route) {}
+
+    ///
+    /// notify the load balancer a request to the chosen element finished
+    ///
+    fn finish(&self, _element: &Self::Element, _elapsed: Duration, _route: &Route) {}
+
+    ///
+    /// report the outcome of a request to the chosen element
+    ///
+    fn record(&self, _element: &Self::Element, _outcome: &Outcome, _route: &Route) {}
+
     ///
     /// Wrap to boxed load balancer
     ///
@@ -69,14 +123,115 @@ where
 {
     type Element = L::Element;
     type Error = L::Error;
-    type Future = BoxFuture<'static, Result<Option<Self::Element>, Self::Error>>;
+    type Future = BoxFuture<'static, Result<Option<Self::Element>, ChooseError<Self::Error>>>;
 
     fn choose(&self, extensions: &mut Extensions) -> Self::Future {
         Box::pin(self.inner.choose(extensions))
     }
+
+    fn start(&self, element: &Self::Element, route: &Route) {
+        self.inner.start(element, route)
+    }
+
+    fn finish(&self, element: &Self::Element, elapsed: Duration, route: &Route) {
+        self.inner.finish(element, elapsed, route)
+    }
+
+    fn record(&self, element: &Self::Element, outcome: &Outcome, route: &Route) {
+        self.inner.record(element, outcome, route)
+    }
+}
+
+///
+/// The load balancer choose error
+///
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum ChooseError<E> {
+    #[error(transparent)]
+    Supplier(E),
+
+    #[error(transparent)]
+    OutOfRange(#[from] OutOfRange),
+}
+
+///
+/// The policy chose an index out of the elements, it's the error of the policy choose
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Policy chose the index {index} out of {len} elements")]
+pub struct OutOfRange {
+    pub index: usize,
+    pub len: usize,
 }
 
-#[derive(Debug, Clone, Default)]
+impl OutOfRange {
+    ///
+    /// Check the chosen index is in the elements
+    ///
+    pub(crate) fn check(index: Option<usize>, len: usize) -> Result<Option<usize>, OutOfRange> {
+        match index {
+            Some(index) if index >= len => Err(OutOfRange { index, len }),
+            index => Ok(index),
+        }
+    }
+}
+
+///
+/// The statistic of a load balancer, it will be passed to the policy when choose, and inserted in
+/// the extensions for the dynamic policies
+///
+#[derive(Clone, Default)]
 pub struct Statistic {
     pub count: Arc<AtomicU64>,
+    ///
+    /// the count of this choose, it's taken when the choose starts, so the concurrent chooses
+    /// never see the same cursor
+    ///
+    pub(crate) cursor: u64,
+    ///
+    /// the generation of the elements, `None` if it's unknown or the elements are a subset
+    ///
+    pub(crate) generation: Option<u64>,
+    pub(crate) rng: Option<Arc<Mutex<Box<dyn RngCore + Send>>>>,
+}
+
+impl Debug for Statistic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Statistic")
+            .field("count", &self.count)
+            .field("cursor", &self.cursor)
+            .field("generation", &self.generation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Statistic {
+    ///
+    /// The count of this choose, e.g. the round robin cursor of the dynamic policy, unlike the
+    /// shared `count` it's not changed by the concurrent chooses
+    ///
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    ///
+    /// Use the load balancer rng, or the thread rng if it's not set
+    ///
+    pub(crate) fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        match &self.rng {
+            Some(rng) => f(&mut **rng.lock().unwrap()),
+            None => f(&mut rand::thread_rng()),
+        }
+    }
+
+    ///
+    /// A random index in `0..len`, it's sampled as u64 so the seeded sequence is portable
+    ///
+    pub(crate) fn random(&self, len: usize) -> usize {
+        self.with_rng(|rng| rng.gen_range(0..len as u64) as usize)
+    }
+
+    pub(crate) fn random_bool(&self, p: f64) -> bool {
+        self.with_rng(|rng| rng.gen_bool(p))
+    }
 }
@@ -0,0 +1,70 @@
+use crate::lb::hash::Version;
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::weight::choose_cursor;
+use crate::lb::{OutOfRange, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct State {
+    version: Version,
+    offset: Option<u64>,
+}
+
+///
+/// Round robin from a random offset, the offset will be randomized again when the elements
+/// changed, so the load balancers started at the same time will not send the requests to the
+/// same element in lockstep
+///
+pub(crate) struct OffsetRoundRobin {
+    state: Mutex<State>,
+}
+
+impl OffsetRoundRobin {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for OffsetRoundRobin {}
+
+impl<I: Hash + 'static> LoadBalancerPolicyTrait<I> for OffsetRoundRobin {
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let len = items.len() as u64;
+        let offset = {
+            let mut state = self.state.lock().unwrap();
+            let changed = state.version.update(items, statistic);
+            match state.offset {
+                Some(offset) if !changed => offset,
+                _ => {
+                    let offset = statistic.random(items.len()) as u64;
+                    state.offset = Some(offset);
+                    offset
+                }
+            }
+        };
+        match extensions.get::<Scale<I>>() {
+            Some(_) => {
+                let weights = Scale::weights(extensions, items, vec![1; items.len()]);
+                Ok(choose_cursor(
+                    weights,
+                    statistic.cursor.wrapping_add(offset),
+                ))
+            }
+            None => Ok(Some(((statistic.cursor % len + offset) % len) as usize)),
+        }
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(OffsetRoundRobin::new()))
+    }
+}
@@ -0,0 +1,141 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::{OutOfRange, Route, Statistic};
+use http::Extensions;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///
+/// The latency of the element which has not been observed yet
+///
+const DEFAULT_RTT: Duration = Duration::from_millis(30);
+
+struct Ewma {
+    ///
+    /// the moving average latency in nanos
+    ///
+    cost: f64,
+
+    ///
+    /// the last update instant of cost
+    ///
+    stamp: Instant,
+
+    ///
+    /// the in flight requests count
+    ///
+    pending: usize,
+}
+
+impl Ewma {
+    fn new(now: Instant) -> Self {
+        Self {
+            cost: DEFAULT_RTT.as_nanos() as f64,
+            stamp: now,
+            pending: 0,
+        }
+    }
+
+    fn decayed(&self, now: Instant, decay: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.stamp).as_nanos() as f64;
+        self.cost * (-elapsed / decay).exp()
+    }
+
+    fn load(&self, now: Instant, decay: f64) -> f64 {
+        self.decayed(now, decay) * (self.pending + 1) as f64
+    }
+
+    fn observe(&mut self, now: Instant, decay: f64, rtt: f64) {
+        // the peak latency will be adopted immediately, then decay slowly
+        self.cost = if rtt > self.cost {
+            rtt
+        } else {
+            let elapsed = now.saturating_duration_since(self.stamp).as_nanos() as f64;
+            let w = (-elapsed / decay).exp();
+            self.cost * w + rtt * (1.0 - w)
+        };
+        self.stamp = now;
+    }
+}
+
+///
+/// Pick two random elements and choose the one with the lower peak ewma latency multiplied by
+/// the in flight requests count
+///
+pub(crate) struct PeakEwma<I> {
+    decay: f64,
+    ewmas: Mutex<HashMap<I, Ewma>>,
+}
+
+impl<I> PeakEwma<I> {
+    pub fn new(decay: Duration) -> Self {
+        Self {
+            decay: (decay.as_nanos() as f64).max(1.0),
+            ewmas: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for PeakEwma<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for PeakEwma<I>
+where
+    I: Hash + Eq + Clone + Send + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let now = Instant::now();
+        let mut ewmas = self.ewmas.lock().unwrap();
+
+        // drop the state of the removed elements
+        if ewmas.len() > items.len() {
+            ewmas.retain(|item, _| items.contains(item));
+        }
+
+        let load = |index: usize| {
+            let load = ewmas
+                .get(&items[index])
+                .map(|ewma| ewma.load(now, self.decay))
+                .unwrap_or(DEFAULT_RTT.as_nanos() as f64);
+            load / Scale::factor(extensions, &items[index]).max(f64::MIN_POSITIVE)
+        };
+        // two distinct random candidates
+        let a = statistic.random(items.len());
+        let b = statistic.random(items.len() - 1);
+        let b = if b >= a { b + 1 } else { b };
+        if load(a) <= load(b) {
+            Ok(Some(a))
+        } else {
+            Ok(Some(b))
+        }
+    }
+
+    fn start(&self, item: &I, _: &Route) {
+        let mut ewmas = self.ewmas.lock().unwrap();
+        ewmas
+            .entry(item.clone())
+            .or_insert_with(|| Ewma::new(Instant::now()))
+            .pending += 1;
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, _: &Route) {
+        let now = Instant::now();
+        let mut ewmas = self.ewmas.lock().unwrap();
+        let ewma = ewmas.entry(item.clone()).or_insert_with(|| Ewma::new(now));
+        ewma.pending = ewma.pending.saturating_sub(1);
+        ewma.observe(now, self.decay, elapsed.as_nanos() as f64);
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(PeakEwma {
+            decay: self.decay,
+            ewmas: Mutex::new(HashMap::new()),
+        }))
+    }
+}
@@ -1,11 +1,30 @@
-use crate::lb::weight::WeightProvider;
-use crate::lb::Statistic;
-use crate::with::With;
+use crate::lb::aperture::{Aperture, DeterministicAperture};
+use crate::lb::fallback::Fallback;
+use crate::lb::least_requests::LeastRequests;
+use crate::lb::least_response_time::LeastResponseTime;
+use crate::lb::locality::Locality;
+use crate::lb::maglev::Maglev;
+use crate::lb::offset_round_robin::OffsetRoundRobin;
+use crate::lb::peak_ewma::PeakEwma;
+use crate::lb::priority::Priority;
+use crate::lb::rendezvous::Rendezvous;
+use crate::lb::ring::ConsistentHash;
+use crate::lb::scale::Scale;
+use crate::lb::server_load::ServerLoad;
+use crate::lb::slow_start::SlowStart;
+use crate::lb::smooth_weight::SmoothWeight;
+use crate::lb::split::Split;
+use crate::lb::stateful::{Stateful, StatefulPolicy};
+use crate::lb::weight::{choose_cursor, choose_weighted, weights, WeightProvider};
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use futures::future::BoxFuture;
 use http::Extensions;
-use rand::Rng;
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 #[derive(Default)]
 pub enum LoadBalancerPolicy<I> {
@@ -16,6 +35,31 @@ pub enum LoadBalancerPolicy<I> {
     Last,
     Weight(Arc<dyn WeightProvider<I> + Send + Sync>),
     Dynamic(Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>),
+    Custom(CustomPolicy<I>),
+}
+
+///
+/// The opaque policy created by the constructors of the [`LoadBalancerPolicy`], e.g.
+/// [`LoadBalancerPolicy::least_requests`]
+///
+pub struct CustomPolicy<I> {
+    name: &'static str,
+    policy: Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>,
+}
+
+impl<I> Clone for CustomPolicy<I> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<I> Debug for CustomPolicy<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
 }
 
 impl<I> Debug for LoadBalancerPolicy<I> {
@@ -27,6 +71,7 @@ impl<I> Debug for LoadBalancerPolicy<I> {
             LoadBalancerPolicy::Last => f.write_str("Last"),
             LoadBalancerPolicy::Weight(_) => f.write_str("Weight(f)"),
             LoadBalancerPolicy::Dynamic(_) => f.write_str("Dynamic(f)"),
+            LoadBalancerPolicy::Custom(policy) => write!(f, "Custom({:?})", policy),
         }
     }
 }
@@ -40,60 +85,523 @@ impl<I> Clone for LoadBalancerPolicy<I> {
             LoadBalancerPolicy::Last => LoadBalancerPolicy::Last,
             LoadBalancerPolicy::Weight(f) => LoadBalancerPolicy::Weight(f.clone()),
             LoadBalancerPolicy::Dynamic(f) => LoadBalancerPolicy::Dynamic(f.clone()),
+            LoadBalancerPolicy::Custom(policy) => LoadBalancerPolicy::Custom(policy.clone()),
         }
     }
 }
 
 impl<I> LoadBalancerPolicy<I> {
+    fn custom<P>(name: &'static str, policy: P) -> Self
+    where
+        P: LoadBalancerPolicyTrait<I> + Send + Sync + 'static,
+    {
+        Self::Custom(CustomPolicy {
+            name,
+            policy: Arc::new(policy),
+        })
+    }
+
+    ///
+    /// the policy with a fresh state, so the load balancers will not share the state
+    ///
+    pub(crate) fn fresh(&self) -> Self {
+        let fork = |f: &Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>| {
+            f.fork().unwrap_or_else(|| f.clone())
+        };
+        match self {
+            LoadBalancerPolicy::Dynamic(f) => LoadBalancerPolicy::Dynamic(fork(f)),
+            LoadBalancerPolicy::Custom(policy) => LoadBalancerPolicy::Custom(CustomPolicy {
+                name: policy.name,
+                policy: fork(&policy.policy),
+            }),
+            _ => self.clone(),
+        }
+    }
+
+    ///
+    /// Choose the element randomly in proportion to the weights, the zero weight will never be
+    /// chosen unless all the weights are zero, then the elements weigh the same.
+    ///
     pub fn weight<F: Fn(&I) -> usize + Send + Sync + 'static>(f: F) -> Self {
         Self::Weight(Arc::new(f))
     }
 
+    ///
+    /// Nginx smooth weighted round robin, e.g. the weights `{a: 5, b: 1, c: 1}` will choose
+    /// `a, a, b, a, c, a, a` rather than the runs of `a`.
+    ///
+    pub fn smooth_weight<F: Fn(&I) -> usize + Send + Sync + 'static>(f: F) -> Self
+    where
+        I: Hash + 'static,
+    {
+        Self::custom("SmoothWeight", SmoothWeight::new(Arc::new(f)))
+    }
+
+    pub fn least_requests() -> Self
+    where
+        I: Hash + Eq + Clone + Send + Sync + 'static,
+    {
+        Self::custom("LeastRequests", LeastRequests::new())
+    }
+
+    ///
+    /// Choose the element with the lowest ratio of the in flight requests to the weight, e.g.
+    /// the weights are the cores of the nodes, the zero weight will never be chosen unless all
+    /// the weights are zero.
+    ///
+    pub fn weighted_least_requests<F>(f: F) -> Self
+    where
+        F: Fn(&I) -> usize + Send + Sync + 'static,
+        I: Hash + Eq + Clone + Send + Sync + 'static,
+    {
+        Self::custom(
+            "WeightedLeastRequests",
+            LeastRequests::weighted(Arc::new(f)),
+        )
+    }
+
+    ///
+    /// Power of two choices over the peak exponentially weighted moving average latency,
+    /// the `decay` is the time window which the observed latency will be decayed in.
+    ///
+    pub fn peak_ewma(decay: Duration) -> Self
+    where
+        I: Hash + Eq + Clone + Send + Sync + 'static,
+    {
+        Self::custom("PeakEwma", PeakEwma::new(decay))
+    }
+
+    ///
+    /// Consistent hash ring with `replicas` virtual nodes per element, the request will be routed
+    /// by the [`HashKey`](crate::HashKey) in the extensions, or a random element if it not exists.
+    ///
+    pub fn consistent_hash(replicas: usize) -> Self
+    where
+        I: Hash,
+    {
+        Self::custom("ConsistentHash", ConsistentHash::new(replicas))
+    }
+
+    ///
+    /// Maglev consistent hash with a lookup table of (at least) `size` entries, the size will be
+    /// rounded up to a prime, and should be much larger than the count of elements, e.g. `65537`.
+    /// The request will be routed by the [`HashKey`](crate::HashKey) in the extensions, or a random
+    /// element if it not exists.
+    ///
+    pub fn maglev(size: usize) -> Self
+    where
+        I: Hash,
+    {
+        Self::custom("Maglev", Maglev::new(size))
+    }
+
+    ///
+    /// Weighted rendezvous hash, the heavier element will take a proportionally larger share of
+    /// the keys. The request will be routed by the [`HashKey`](crate::HashKey) in the extensions,
+    /// or a random element by the weights if it not exists.
+    ///
+    pub fn rendezvous<F: Fn(&I) -> usize + Send + Sync + 'static>(f: F) -> Self
+    where
+        I: Hash + 'static,
+    {
+        Self::custom("Rendezvous", Rendezvous::new(Arc::new(f)))
+    }
+
+    ///
+    /// Split the traffic between the element groups by the weights, see [`Split`]
+    ///
+    pub fn split(split: Split<I>) -> Self
+    where
+        I: Clone + Send + Sync + 'static,
+    {
+        Self::custom("Split", split)
+    }
+
+    ///
+    /// Route to the healthy elements of the highest priority tier, see [`Priority`]
+    ///
+    pub fn priority(priority: Priority<I>) -> Self
+    where
+        I: Clone + Send + Sync + 'static,
+    {
+        Self::custom("Priority", priority)
+    }
+
+    ///
+    /// Keep the traffic in the local zone, and spill to the other zones, see [`Locality`]
+    ///
+    pub fn locality(locality: Locality<I>) -> Self
+    where
+        I: Clone + Send + Sync + 'static,
+    {
+        Self::custom("Locality", locality)
+    }
+
+    ///
+    /// Choose the element with the lowest latency `percentile` (e.g. `0.5` or `0.9`) in the
+    /// sliding `window`, and choose a random element at the `exploration` rate (e.g. `0.05`).
+    ///
+    pub fn least_response_time(percentile: f64, window: Duration, exploration: f64) -> Self
+    where
+        I: Hash + Eq + Clone + Send + Sync + 'static,
+    {
+        Self::custom(
+            "LeastResponseTime",
+            LeastResponseTime::new(percentile, window, exploration),
+        )
+    }
+
+    ///
+    /// Decline the request without the [`HashKey`](crate::HashKey) rather than choose a random
+    /// element, it only affects the hash policies. The policies of the [`fallback`](Self::fallback)
+    /// except the last one will decline without the key.
+    ///
+    pub fn decline_without_key(self) -> Self {
+        match self {
+            LoadBalancerPolicy::Custom(policy) => match policy.policy.decline_without_key() {
+                Some(declined) => LoadBalancerPolicy::Custom(CustomPolicy {
+                    name: policy.name,
+                    policy: declined,
+                }),
+                None => LoadBalancerPolicy::Custom(policy),
+            },
+            policy => policy,
+        }
+    }
+
+    ///
+    /// Try the policies in order, and fall through to the next one when the policy declines,
+    /// e.g. consistent hash if the key exists, otherwise least requests. The hooks of a request
+    /// are sent to the policy which chose the element.
+    ///
+    pub fn fallback<P>(policies: P) -> Self
+    where
+        P: IntoIterator<Item = LoadBalancerPolicy<I>>,
+        I: Hash + Eq + Clone + Send + 'static,
+    {
+        let policies = policies.into_iter().collect::<Vec<_>>();
+        let last = policies.len().saturating_sub(1);
+        // the last policy takes the request without the key, as the standalone one
+        let policies = policies
+            .into_iter()
+            .enumerate()
+            .map(|(index, policy)| {
+                if index < last {
+                    policy.decline_without_key()
+                } else {
+                    policy
+                }
+            })
+            .collect();
+        Self::custom("Fallback", Fallback::new(policies))
+    }
+
+    ///
+    /// The user defined policy with its own state in each load balancer
+    ///
+    pub fn stateful<P>(policy: P) -> Self
+    where
+        P: StatefulPolicy<I> + Send + Sync + 'static,
+        I: Hash + Eq + Clone + Send + 'static,
+    {
+        Self::custom("Stateful", Stateful::new(Arc::new(policy)))
+    }
+
+    ///
+    /// Talk to a small window of the elements which widens under load, see [`Aperture`]
+    ///
+    pub fn aperture(aperture: Aperture) -> Self
+    where
+        I: Hash + Eq + Clone + Send + 'static,
+    {
+        Self::custom("Aperture", DeterministicAperture::new(aperture))
+    }
+
+    ///
+    /// Scale the weights (or the loads) of the `policy` by `1 / (1 + load)`, e.g. `Random` or
+    /// `least_requests()`, the load is reported by the element (see
+    /// [`LoadBalancerMiddleware::load_header`](crate::LoadBalancerMiddleware::load_header)),
+    /// and it decays to zero with the `decay` (e.g. `10s`) without the new reports. The scale is
+    /// honored by the same policies as [`SlowStart`].
+    ///
+    pub fn server_load(policy: LoadBalancerPolicy<I>, decay: Duration) -> Self
+    where
+        I: Hash + Eq + Clone + Send + Sync + 'static,
+    {
+        Self::custom("ServerLoad", ServerLoad::new(policy, decay))
+    }
+
+    ///
+    /// Ramp up the traffic of the newly added elements, see [`SlowStart`]
+    ///
+    pub fn slow_start(slow_start: SlowStart<I>) -> Self
+    where
+        I: Hash + Eq + Clone + Send + Sync + 'static,
+    {
+        Self::custom("SlowStart", slow_start)
+    }
+
+    ///
+    /// Round robin from a random offset, which will be randomized again when the elements
+    /// changed, use [`LoadBalancer::rng`](crate::supplier::LoadBalancer::rng) to seed it per process.
+    ///
+    pub fn offset_round_robin() -> Self
+    where
+        I: Hash + 'static,
+    {
+        Self::custom("OffsetRoundRobin", OffsetRoundRobin::new())
+    }
+
     pub fn dynamic<F: Fn(&[I], &Extensions) -> usize + Send + Sync + 'static>(f: F) -> Self {
         Self::Dynamic(Arc::new(f))
     }
+
+    ///
+    /// The dynamic policy which can decline by returning `None`, e.g. in the
+    /// [`fallback`](Self::fallback)
+    ///
+    pub fn try_dynamic<F>(f: F) -> Self
+    where
+        F: Fn(&[I], &Extensions) -> Option<usize> + Send + Sync + 'static,
+    {
+        Self::Dynamic(Arc::new(TryDynamic(f)))
+    }
 }
 
 pub trait LoadBalancerPolicyTrait<I>: sealed::Sealed<I> {
-    fn choose(&self, items: &[I], extensions: &mut Extensions) -> usize;
+    ///
+    /// choose the index of the element, return `None` if no element can be chosen, or the error
+    /// if the nested policy chose an index out of the elements
+    ///
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange>;
+
+    ///
+    /// a request to the chosen element started
+    ///
+    fn start(&self, _item: &I, _route: &Route) {}
+
+    ///
+    /// a request to the chosen element finished
+    ///
+    fn finish(&self, _item: &I, _elapsed: Duration, _route: &Route) {}
+
+    ///
+    /// the outcome of a request to the chosen element reported
+    ///
+    fn record(&self, _item: &I, _outcome: &Outcome, _route: &Route) {}
+
+    ///
+    /// create the policy with the same configuration and a fresh state for a new load balancer,
+    /// return `None` if the policy is stateless and can be shared
+    ///
+    #[doc(hidden)]
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        None
+    }
+
+    ///
+    /// create the policy declines the request without the [`HashKey`](crate::HashKey), return
+    /// `None` if the policy doesn't route by the key
+    ///
+    #[doc(hidden)]
+    fn decline_without_key(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        None
+    }
+}
+
+///
+/// The async variant of [`LoadBalancerPolicyTrait`], the load balancer drives the future after
+/// the supplier future resolved, see
+/// [`LoadBalancer::with_async_policy`](crate::supplier::LoadBalancer::with_async_policy). The
+/// future owns the elements and resolves the chosen one, `None` if no element can be chosen.
+///
+pub trait AsyncLoadBalancerPolicyTrait<I>: sealed::AsyncSealed<I> {
+    fn choose(&self, items: Vec<I>, extensions: Extensions) -> BoxFuture<'static, Option<I>>;
+}
+
+impl<I, F, Fut> sealed::AsyncSealed<I> for F
+where
+    F: Fn(Vec<I>, Extensions) -> Fut,
+    Fut: Future<Output = Option<I>> + Send + 'static,
+{
+}
+
+impl<I, F, Fut> AsyncLoadBalancerPolicyTrait<I> for F
+where
+    F: Fn(Vec<I>, Extensions) -> Fut,
+    Fut: Future<Output = Option<I>> + Send + 'static,
+{
+    fn choose(&self, items: Vec<I>, extensions: Extensions) -> BoxFuture<'static, Option<I>> {
+        Box::pin(self(items, extensions))
+    }
 }
 
 impl<I> sealed::Sealed<I> for LoadBalancerPolicy<I> {}
 
-impl<I> LoadBalancerPolicyTrait<I> for LoadBalancerPolicy<I> {
-    fn choose(&self, items: &[I], extensions: &mut Extensions) -> usize {
+impl<I: 'static> LoadBalancerPolicyTrait<I> for LoadBalancerPolicy<I> {
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
         let len = items.len();
-        assert!(len > 1);
+        if len == 0 {
+            return Ok(None);
+        }
         match self {
-            LoadBalancerPolicy::RoundRobin => match extensions.get::<Statistic>() {
-                Some(statistic) => {
-                    let count = statistic.count.load(Ordering::Relaxed).saturating_sub(1);
-                    (count % (len as u64)) as usize
+            LoadBalancerPolicy::RoundRobin => match extensions.get::<Scale<I>>() {
+                Some(_) => {
+                    let weights = Scale::weights(extensions, items, vec![1; len]);
+                    Ok(choose_cursor(weights, statistic.cursor))
                 }
-                None => 0,
+                None => Ok(Some((statistic.cursor % (len as u64)) as usize)),
             },
-            LoadBalancerPolicy::Random => rand::thread_rng().gen_range(0..len),
-            LoadBalancerPolicy::First => 0,
-            LoadBalancerPolicy::Last => items.len() - 1,
+            LoadBalancerPolicy::Random => match extensions.get::<Scale<I>>() {
+                Some(_) => {
+                    let weights = Scale::weights(extensions, items, vec![1; len]);
+                    Ok(statistic.with_rng(|rng| choose_weighted(weights, rng)))
+                }
+                None => Ok(Some(statistic.random(len))),
+            },
+            LoadBalancerPolicy::First => Ok(Some(0)),
+            LoadBalancerPolicy::Last => Ok(Some(items.len() - 1)),
             LoadBalancerPolicy::Weight(f) => {
-                let indexes = items
-                    .iter()
-                    .enumerate()
-                    .map(|(index, item)| (index, f.weight(item)))
-                    .flat_map(|(index, len)| {
-                        Vec::with_capacity(len).with(|c| {
-                            for _ in 0..len {
-                                c.push(index);
-                            }
-                        })
-                    })
-                    .collect::<Vec<_>>();
-                let index = rand::thread_rng().gen_range(0..indexes.len());
-                indexes[index]
+                let weights = Scale::weights(extensions, items, weights(&**f, items));
+                Ok(statistic.with_rng(|rng| choose_weighted(weights, rng)))
+            }
+            LoadBalancerPolicy::Dynamic(f) => f.choose(items, statistic, extensions),
+            LoadBalancerPolicy::Custom(policy) => {
+                policy.policy.choose(items, statistic, extensions)
             }
-            LoadBalancerPolicy::Dynamic(f) => f.choose(items, extensions),
         }
     }
+
+    fn start(&self, item: &I, route: &Route) {
+        match self {
+            LoadBalancerPolicy::Dynamic(f) => f.start(item, route),
+            LoadBalancerPolicy::Custom(policy) => policy.policy.start(item, route),
+            _ => {}
+        }
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        match self {
+            LoadBalancerPolicy::Dynamic(f) => f.finish(item, elapsed, route),
+            LoadBalancerPolicy::Custom(policy) => policy.policy.finish(item, elapsed, route),
+            _ => {}
+        }
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        match self {
+            LoadBalancerPolicy::Dynamic(f) => f.record(item, outcome, route),
+            LoadBalancerPolicy::Custom(policy) => policy.policy.record(item, outcome, route),
+            _ => {}
+        }
+    }
+}
+
+///
+/// The nested policy of the subsets (e.g. the groups of the split), it's forked for each subset,
+/// so the state of the nested policy is kept per subset rather than reset by every flip between
+/// the subsets, and the hooks of the element go to the fork of its subset
+///
+pub(crate) struct Subsets<K, I> {
+    policy: LoadBalancerPolicy<I>,
+    forks: Mutex<HashMap<K, LoadBalancerPolicy<I>>>,
+}
+
+impl<K, I> Subsets<K, I>
+where
+    K: Hash + Eq,
+    I: Clone + 'static,
+{
+    pub fn new(policy: LoadBalancerPolicy<I>) -> Self {
+        Self {
+            policy,
+            forks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    ///
+    /// The nested policy before forked
+    ///
+    pub fn policy(&self) -> &LoadBalancerPolicy<I> {
+        &self.policy
+    }
+
+    ///
+    /// The fork of the subset, `None` if the subset has never been chosen from
+    ///
+    pub fn get(&self, key: &K) -> Option<LoadBalancerPolicy<I>> {
+        self.forks.lock().unwrap().get(key).cloned()
+    }
+
+    ///
+    /// Use the fork of the subset choose from the subset of the items, return the index of the
+    /// items, the index out of the subset is an error rather than a decline
+    ///
+    pub fn choose(
+        &self,
+        key: K,
+        items: &[I],
+        indexes: &[usize],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        if indexes.is_empty() {
+            return Ok(None);
+        }
+        let policy = self
+            .forks
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| self.policy.fresh())
+            .clone();
+        if indexes.len() == 1 {
+            return Ok(Some(indexes[0]));
+        }
+        let subset = indexes
+            .iter()
+            .map(|index| items[*index].clone())
+            .collect::<Vec<_>>();
+        // the generation is of all the elements, not the subset
+        let statistic = Statistic {
+            generation: None,
+            ..statistic.clone()
+        };
+        let index = policy.choose(&subset, &statistic, extensions)?;
+        Ok(OutOfRange::check(index, indexes.len())?.map(|index| indexes[index]))
+    }
+}
+
+///
+/// The dynamic policy may decline, it's a wrapper since the closures can't be told apart by the
+/// output type
+///
+struct TryDynamic<F>(F);
+
+impl<I, F> sealed::Sealed<I> for TryDynamic<F> where F: Fn(&[I], &Extensions) -> Option<usize> {}
+
+impl<I, F> LoadBalancerPolicyTrait<I> for TryDynamic<F>
+where
+    F: Fn(&[I], &Extensions) -> Option<usize>,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        _: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        OutOfRange::check((self.0)(items, extensions), items.len())
+    }
 }
 
 impl<I, F> sealed::Sealed<I> for F where F: Fn(&[I], &Extensions) -> usize {}
@@ -102,11 +610,18 @@ impl<I, F> LoadBalancerPolicyTrait<I> for F
 where
     F: Fn(&[I], &Extensions) -> usize,
 {
-    fn choose(&self, items: &[I], extensions: &mut Extensions) -> usize {
-        self(items, extensions)
+    fn choose(
+        &self,
+        items: &[I],
+        _: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        OutOfRange::check(Some(self(items, extensions)), items.len())
     }
 }
 
-mod sealed {
+pub(crate) mod sealed {
     pub trait Sealed<I> {}
+
+    pub trait AsyncSealed<I> {}
 }
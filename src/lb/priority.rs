@@ -0,0 +1,159 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicy, LoadBalancerPolicyTrait, Subsets};
+use crate::lb::weight::choose_weighted;
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+///
+/// The default overprovisioning factor, same as envoy
+///
+const DEFAULT_OVERPROVISIONING: f64 = 1.4;
+
+///
+/// Group the elements into the priority tiers (the lower value has the higher priority), the
+/// policy only chooses from the healthy elements of the highest priority tier. When the healthy
+/// share of the tier multiplied by the overprovisioning factor drops below 100%, the next tier
+/// takes the rest traffic gradually, as envoy does.
+///
+/// If there is no healthy element in all tiers, all the elements will be treated as healthy. Each
+/// tier has its own state of the policy.
+///
+/// ```
+/// use reqwest_lb::{LoadBalancerPolicy, Priority};
+///
+/// // the port 3000 is primary, the others are secondary
+/// let priority = Priority::new(LoadBalancerPolicy::RoundRobin, |port: &u16| {
+///     if *port == 3000 { 0 } else { 1 }
+/// })
+/// .healthy(|port: &u16| *port != 3001);
+/// let policy = LoadBalancerPolicy::priority(priority);
+/// ```
+///
+pub struct Priority<I> {
+    priority: Arc<dyn Fn(&I) -> usize + Send + Sync>,
+    healthy: Arc<dyn Fn(&I) -> bool + Send + Sync>,
+    overprovisioning: f64,
+    subsets: Arc<Subsets<usize, I>>,
+}
+
+impl<I> Clone for Priority<I> {
+    fn clone(&self) -> Self {
+        Self {
+            priority: self.priority.clone(),
+            healthy: self.healthy.clone(),
+            overprovisioning: self.overprovisioning,
+            subsets: self.subsets.clone(),
+        }
+    }
+}
+
+impl<I: Clone + 'static> Priority<I> {
+    pub fn new<F>(policy: LoadBalancerPolicy<I>, priority: F) -> Self
+    where
+        F: Fn(&I) -> usize + Send + Sync + 'static,
+    {
+        Self {
+            priority: Arc::new(priority),
+            healthy: Arc::new(|_| true),
+            overprovisioning: DEFAULT_OVERPROVISIONING,
+            subsets: Arc::new(Subsets::new(policy)),
+        }
+    }
+
+    ///
+    /// Set the element health check, all the elements are healthy by default
+    ///
+    pub fn healthy<F>(mut self, healthy: F) -> Self
+    where
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        self.healthy = Arc::new(healthy);
+        self
+    }
+
+    ///
+    /// Set the overprovisioning factor, default is `1.4`
+    ///
+    pub fn overprovisioning(mut self, overprovisioning: f64) -> Self {
+        self.overprovisioning = overprovisioning.max(1.0);
+        self
+    }
+}
+
+impl<I> sealed::Sealed<I> for Priority<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for Priority<I>
+where
+    I: Clone + Send + Sync + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        // the tiers ordered by priority: (all indexes, healthy indexes)
+        let mut tiers = BTreeMap::<usize, (Vec<usize>, Vec<usize>)>::new();
+        for (index, item) in items.iter().enumerate() {
+            let tier = tiers.entry((self.priority)(item)).or_default();
+            tier.0.push(index);
+            if (self.healthy)(item) {
+                tier.1.push(index);
+            }
+        }
+        let mut tiers = tiers.into_iter().collect::<Vec<_>>();
+
+        let loads = if tiers.iter().all(|(_, (_, healthy))| healthy.is_empty()) {
+            // panic mode: no healthy element at all, spread the load to all the elements
+            for (_, (all, healthy)) in tiers.iter_mut() {
+                *healthy = all.clone();
+            }
+            tiers.iter().map(|(_, (all, _))| all.len()).collect()
+        } else {
+            // allocate the load (in permille) to the tiers by the overprovisioned health
+            let mut remaining = 1000usize;
+            tiers
+                .iter()
+                .map(|(_, (all, healthy))| {
+                    let health = healthy.len() as f64 / all.len() as f64 * self.overprovisioning;
+                    let load = ((health * 1000.0) as usize).min(remaining);
+                    remaining -= load;
+                    load
+                })
+                .collect::<Vec<_>>()
+        };
+        let Some(tier) = statistic.with_rng(|rng| choose_weighted(loads, rng)) else {
+            return Ok(None);
+        };
+        let (priority, (_, healthy)) = &tiers[tier];
+        self.subsets
+            .choose(*priority, items, healthy, statistic, extensions)
+    }
+
+    fn start(&self, item: &I, route: &Route) {
+        if let Some(policy) = self.subsets.get(&(self.priority)(item)) {
+            policy.start(item, route)
+        }
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        if let Some(policy) = self.subsets.get(&(self.priority)(item)) {
+            policy.finish(item, elapsed, route)
+        }
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        if let Some(policy) = self.subsets.get(&(self.priority)(item)) {
+            policy.record(item, outcome, route)
+        }
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Priority {
+            subsets: Arc::new(Subsets::new(self.subsets.policy().clone())),
+            ..self.clone()
+        }))
+    }
+}
@@ -0,0 +1,68 @@
+use crate::lb::hash::{hash, HashKey};
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::weight::{choose_weighted, weights, WeightProvider};
+use crate::lb::{OutOfRange, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::Arc;
+
+///
+/// Weighted rendezvous (highest random weight) hash, score every element against the
+/// [`HashKey`] in the extensions and choose the highest one. The request without the key will
+/// be routed to a random element by the weights, or declined if `decline` is set.
+///
+pub(crate) struct Rendezvous<I> {
+    weight: Arc<dyn WeightProvider<I> + Send + Sync>,
+    decline: bool,
+}
+
+impl<I> Rendezvous<I> {
+    pub fn new(weight: Arc<dyn WeightProvider<I> + Send + Sync>) -> Self {
+        Self {
+            weight,
+            decline: false,
+        }
+    }
+}
+
+fn score<I: Hash>(key: u64, item: &I, weight: usize) -> f64 {
+    // map the hash into (0, 1), then the score is `-weight / ln(hash)`
+    let hash = (hash(&(key, item)) >> 11) as f64;
+    let unit = (hash + 1.0) / ((1u64 << 53) as f64 + 1.0);
+    -(weight as f64) / unit.ln()
+}
+
+impl<I> sealed::Sealed<I> for Rendezvous<I> {}
+
+impl<I: Hash + 'static> LoadBalancerPolicyTrait<I> for Rendezvous<I> {
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let Some(key) = extensions.get::<HashKey>() else {
+            if self.decline {
+                return Ok(None);
+            }
+            let weights = weights(&*self.weight, items);
+            return Ok(statistic.with_rng(|rng| choose_weighted(weights, rng)));
+        };
+        // the zero weight element will never be chosen
+        Ok(items
+            .iter()
+            .zip(weights(&*self.weight, items))
+            .map(|(item, weight)| score(key.0, item, weight))
+            .enumerate()
+            .filter(|(_, score)| *score > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index))
+    }
+
+    fn decline_without_key(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Rendezvous {
+            weight: self.weight.clone(),
+            decline: true,
+        }))
+    }
+}
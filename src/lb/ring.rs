@@ -0,0 +1,86 @@
+use crate::lb::hash::{hash, HashKey, Version};
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::{OutOfRange, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Ring {
+    points: Vec<(u64, usize)>,
+}
+
+impl Ring {
+    fn build<I: Hash>(items: &[I], replicas: usize) -> Self {
+        let mut points = Vec::with_capacity(items.len() * replicas);
+        for (index, item) in items.iter().enumerate() {
+            for replica in 0..replicas {
+                points.push((hash(&(item, replica)), index));
+            }
+        }
+        points.sort_unstable();
+        Self { points }
+    }
+
+    fn find(&self, key: u64) -> usize {
+        let position = self.points.partition_point(|(point, _)| *point < key);
+        self.points[position % self.points.len()].1
+    }
+}
+
+///
+/// Ketama style consistent hash ring, each element has `replicas` virtual nodes on the ring,
+/// the request will be routed by the [`HashKey`] in the extensions, the request without the key
+/// will be routed to a random element, or declined if `decline` is set.
+///
+pub(crate) struct ConsistentHash {
+    replicas: usize,
+    decline: bool,
+    ring: Mutex<(Version, Ring)>,
+}
+
+impl ConsistentHash {
+    pub fn new(replicas: usize) -> Self {
+        Self {
+            replicas: replicas.max(1),
+            decline: false,
+            ring: Mutex::new((Version::default(), Ring::default())),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for ConsistentHash {}
+
+impl<I: Hash> LoadBalancerPolicyTrait<I> for ConsistentHash {
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let Some(key) = extensions.get::<HashKey>() else {
+            return Ok((!self.decline).then(|| statistic.random(items.len())));
+        };
+        let mut ring = self.ring.lock().unwrap();
+        let (version, ring) = &mut *ring;
+        // rebuild the ring only when the elements changed
+        if version.update(items, statistic) {
+            *ring = Ring::build(items, self.replicas);
+        }
+        Ok(Some(ring.find(key.0)))
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(ConsistentHash {
+            decline: self.decline,
+            ..ConsistentHash::new(self.replicas)
+        }))
+    }
+
+    fn decline_without_key(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(ConsistentHash {
+            decline: true,
+            ..ConsistentHash::new(self.replicas)
+        }))
+    }
+}
@@ -0,0 +1,76 @@
+use http::Extensions;
+use std::sync::Arc;
+
+///
+/// The resolution of the scaled weights
+///
+const RESOLUTION: f64 = 1_000.0;
+
+///
+/// The factors of the elements passed to the inner policy in the extensions, the weighted
+/// policies multiply the weight of the element by the factor, and the least load policies divide
+/// the load by it. It's how the wrappers (e.g. the slow start) steer any weighted policy without
+/// the knowledge of its state.
+///
+pub(crate) struct Scale<I>(Arc<dyn Fn(&I) -> f64 + Send + Sync>);
+
+impl<I> Clone for Scale<I> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<I: 'static> Scale<I> {
+    ///
+    /// The factor of the element, `1.0` if there is no scale
+    ///
+    pub fn factor(extensions: &Extensions, item: &I) -> f64 {
+        extensions
+            .get::<Scale<I>>()
+            .map_or(1.0, |scale| (scale.0)(item))
+    }
+
+    ///
+    /// Scale the weights of the elements, the weights are kept if there is no scale, the positive
+    /// weight is at least `1` after scaled, so the elements are not all declined by the weighted
+    /// policies
+    ///
+    pub fn weights(extensions: &Extensions, items: &[I], weights: Vec<usize>) -> Vec<usize> {
+        match extensions.get::<Scale<I>>() {
+            Some(scale) => items
+                .iter()
+                .zip(weights)
+                .map(|(item, weight)| match weight {
+                    0 => 0,
+                    weight => ((weight as f64 * (scale.0)(item) * RESOLUTION) as usize).max(1),
+                })
+                .collect(),
+            None => weights,
+        }
+    }
+
+    ///
+    /// Run the choose with the factors, they are multiplied by the factors of the outer wrappers
+    ///
+    pub fn with<F, T>(
+        extensions: &mut Extensions,
+        factor: F,
+        choose: impl FnOnce(&mut Extensions) -> T,
+    ) -> T
+    where
+        F: Fn(&I) -> f64 + Send + Sync + 'static,
+    {
+        let outer = extensions.remove::<Scale<I>>();
+        let scale = match outer.clone() {
+            Some(outer) => Scale(Arc::new(move |item: &I| (outer.0)(item) * factor(item))),
+            None => Scale(Arc::new(factor)),
+        };
+        extensions.insert(scale);
+        let result = choose(extensions);
+        match outer {
+            Some(outer) => extensions.insert(outer),
+            None => extensions.remove::<Scale<I>>(),
+        };
+        result
+    }
+}
@@ -0,0 +1,126 @@
+use crate::lb::feedback::LOAD_RESOLUTION;
+use crate::lb::policy::{sealed, LoadBalancerPolicy, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+struct Report {
+    ///
+    /// the moving average of the reported load
+    ///
+    load: f64,
+
+    ///
+    /// the last report instant
+    ///
+    stamp: Instant,
+}
+
+impl Report {
+    fn decayed(&self, now: Instant, decay: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.stamp).as_nanos() as f64;
+        self.load * (-elapsed / decay).exp()
+    }
+}
+
+///
+/// Scale the weights (or the loads) of the inner policy by `1 / (1 + load)`, the load is the moving
+/// average of the load reported by the element, and it decays to zero without the new reports,
+/// so the element overloaded before will get the traffic back gradually.
+///
+pub(crate) struct ServerLoad<I> {
+    decay: f64,
+    policy: LoadBalancerPolicy<I>,
+    reports: Arc<Mutex<HashMap<I, Report>>>,
+}
+
+impl<I> ServerLoad<I> {
+    pub fn new(policy: LoadBalancerPolicy<I>, decay: Duration) -> Self {
+        Self {
+            decay: (decay.as_nanos() as f64).max(1.0),
+            policy,
+            reports: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for ServerLoad<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for ServerLoad<I>
+where
+    I: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        {
+            let mut reports = self.reports.lock().unwrap();
+            if reports.is_empty() {
+                return self.policy.choose(items, statistic, extensions);
+            }
+            // drop the reports of the removed elements
+            if reports.len() > items.len() {
+                reports.retain(|item, _| items.contains(item));
+            }
+        }
+
+        let now = Instant::now();
+        let decay = self.decay;
+        let reports = self.reports.clone();
+        let factor = move |item: &I| {
+            let load = reports
+                .lock()
+                .unwrap()
+                .get(item)
+                .map(|report| report.decayed(now, decay))
+                .unwrap_or_default();
+            1.0 / (1.0 + load)
+        };
+        Scale::with(extensions, factor, |extensions| {
+            self.policy.choose(items, statistic, extensions)
+        })
+    }
+
+    fn start(&self, item: &I, route: &Route) {
+        self.policy.start(item, route)
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        self.policy.finish(item, elapsed, route)
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        self.policy.record(item, outcome, route);
+        let Some(load) = outcome.load.map(|load| load as f64 / LOAD_RESOLUTION) else {
+            return;
+        };
+        let now = Instant::now();
+        let mut reports = self.reports.lock().unwrap();
+        match reports.get_mut(item) {
+            Some(report) => {
+                let elapsed = now.saturating_duration_since(report.stamp).as_nanos() as f64;
+                let w = (-elapsed / self.decay).exp();
+                report.load = report.load * w + load * (1.0 - w);
+                report.stamp = now;
+            }
+            None => {
+                reports.insert(item.clone(), Report { load, stamp: now });
+            }
+        }
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(ServerLoad {
+            decay: self.decay,
+            policy: self.policy.fresh(),
+            reports: Arc::new(Mutex::new(HashMap::new())),
+        }))
+    }
+}
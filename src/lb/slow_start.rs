@@ -0,0 +1,205 @@
+use crate::lb::policy::{sealed, LoadBalancerPolicy, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///
+/// The default min factor of the new element
+///
+const DEFAULT_MIN_FACTOR: f64 = 0.1;
+
+///
+/// The instant the element was added, `None` if it's an initial element
+///
+type Since<I> = Arc<dyn Fn(&I) -> Option<Instant> + Send + Sync>;
+
+///
+/// The instants the elements first chosen, `None` before the first choose
+///
+type Added<I> = Arc<Mutex<Option<HashMap<I, Option<Instant>>>>>;
+
+///
+/// Ramp up the traffic of the newly added elements over the window, the weight (or the load) of
+/// the new element is scaled by the factor `max((elapsed / window) ^ (1 / aggression), min_factor)`
+/// in the inner policy, so the state of the inner policy is kept. The factor is honored by the
+/// `RoundRobin`, `OffsetRoundRobin`, `Random`, `Weight`, `SmoothWeight`, `LeastRequests`,
+/// `PeakEwma` and `LeastResponseTime` policies, the round robin policies turn to the weighted
+/// order of the cursor while any element is warming, the others ignore it.
+///
+/// The element is added when the [`since`](SlowStart::since) instant, e.g. the
+/// [`DiscoverySupplier::inserted_by`](crate::supplier::DiscoverySupplier::inserted_by) instant,
+/// or when it's chosen from the first time by default, and the elements of the first choose are
+/// the initial elements, they will not be ramped up. Each load balancer keeps its own state, the
+/// clones of the policy share it.
+///
+/// ```
+/// use reqwest_lb::{LoadBalancerPolicy, SlowStart};
+/// use std::time::Duration;
+///
+/// let slow_start = SlowStart::new(LoadBalancerPolicy::least_requests(), Duration::from_secs(60))
+///     .aggression(2.0)
+///     .min_factor(0.05);
+/// let policy = LoadBalancerPolicy::<u16>::slow_start(slow_start);
+/// ```
+///
+pub struct SlowStart<I> {
+    window: Duration,
+    aggression: f64,
+    min_factor: f64,
+    policy: LoadBalancerPolicy<I>,
+    since: Option<Since<I>>,
+    added: Added<I>,
+}
+
+impl<I> Clone for SlowStart<I> {
+    fn clone(&self) -> Self {
+        Self {
+            window: self.window,
+            aggression: self.aggression,
+            min_factor: self.min_factor,
+            policy: self.policy.clone(),
+            since: self.since.clone(),
+            added: self.added.clone(),
+        }
+    }
+}
+
+impl<I> SlowStart<I> {
+    pub fn new(policy: LoadBalancerPolicy<I>, window: Duration) -> Self {
+        Self {
+            window,
+            aggression: 1.0,
+            min_factor: DEFAULT_MIN_FACTOR,
+            policy,
+            since: None,
+            added: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    ///
+    /// Set the instant the element was added, `None` if it's an initial element, e.g. the
+    /// [`DiscoverySupplier::inserted_by`](crate::supplier::DiscoverySupplier::inserted_by) instant
+    ///
+    pub fn since<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&I) -> Option<Instant> + Send + Sync + 'static,
+    {
+        self.since = Some(Arc::new(f));
+        self
+    }
+
+    ///
+    /// Set the aggression of the ramp curve, default is `1.0` which is linear, the larger
+    /// aggression ramps up faster at the beginning
+    ///
+    pub fn aggression(mut self, aggression: f64) -> Self {
+        if aggression.is_finite() && aggression > 0.0 {
+            self.aggression = aggression;
+        }
+        self
+    }
+
+    ///
+    /// Set the min factor of the new element, default is `0.1`
+    ///
+    pub fn min_factor(mut self, min_factor: f64) -> Self {
+        self.min_factor = min_factor.clamp(0.0, 1.0);
+        self
+    }
+
+    fn factor(&self, elapsed: Duration) -> f64 {
+        let progress = elapsed.as_secs_f64() / self.window.as_secs_f64();
+        progress.powf(1.0 / self.aggression).max(self.min_factor)
+    }
+}
+
+impl<I> sealed::Sealed<I> for SlowStart<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for SlowStart<I>
+where
+    I: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let now = Instant::now();
+        let factor = |stamp: Option<Instant>| match stamp {
+            Some(stamp) if now.saturating_duration_since(stamp) < self.window => {
+                Some(self.factor(now.saturating_duration_since(stamp)))
+            }
+            _ => None,
+        };
+        let warming = match &self.since {
+            Some(since) => items
+                .iter()
+                .filter_map(|item| Some((item.clone(), factor(since(item))?)))
+                .collect::<HashMap<_, _>>(),
+            None => {
+                let mut added = self.added.lock().unwrap();
+                let initial = added.is_none();
+                let added = added.get_or_insert_with(HashMap::new);
+
+                // drop the removed elements, so they will be ramped up again when added back
+                if added.len() > items.len() {
+                    added.retain(|item, _| items.contains(item));
+                }
+                let mut warming = HashMap::new();
+                for item in items {
+                    // the initial elements are warmed up already
+                    let stamp = match added.get_mut(item) {
+                        Some(stamp) => stamp,
+                        None => added
+                            .entry(item.clone())
+                            .or_insert((!initial).then_some(now)),
+                    };
+                    match factor(*stamp) {
+                        Some(factor) => {
+                            warming.insert(item.clone(), factor);
+                        }
+                        None => *stamp = None,
+                    }
+                }
+                warming
+            }
+        };
+        if warming.is_empty() {
+            return self.policy.choose(items, statistic, extensions);
+        }
+
+        // scale the warming elements in the inner policy
+        let warming = Arc::new(warming);
+        Scale::with(
+            extensions,
+            move |item| warming.get(item).copied().unwrap_or(1.0),
+            |extensions| self.policy.choose(items, statistic, extensions),
+        )
+    }
+
+    fn start(&self, item: &I, route: &Route) {
+        self.policy.start(item, route)
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        self.policy.finish(item, elapsed, route)
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        self.policy.record(item, outcome, route)
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        // the load balancers supply different elements, each one ramps up its own
+        Some(Arc::new(SlowStart {
+            policy: self.policy.fresh(),
+            added: Arc::new(Mutex::new(None)),
+            ..self.clone()
+        }))
+    }
+}
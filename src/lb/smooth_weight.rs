@@ -0,0 +1,78 @@
+use crate::lb::hash::Version;
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::scale::Scale;
+use crate::lb::weight::{weights, WeightProvider};
+use crate::lb::{OutOfRange, Statistic};
+use http::Extensions;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct State {
+    version: Version,
+    current: Vec<i128>,
+}
+
+///
+/// Nginx smooth weighted round robin, interleave the heavier element with the others evenly
+///
+pub(crate) struct SmoothWeight<I> {
+    weight: Arc<dyn WeightProvider<I> + Send + Sync>,
+    state: Mutex<State>,
+}
+
+impl<I> SmoothWeight<I> {
+    pub fn new(weight: Arc<dyn WeightProvider<I> + Send + Sync>) -> Self {
+        Self {
+            weight,
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl<I> sealed::Sealed<I> for SmoothWeight<I> {}
+
+impl<I: Hash + 'static> LoadBalancerPolicyTrait<I> for SmoothWeight<I> {
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let mut state = self.state.lock().unwrap();
+
+        // reset the running weights when the elements changed
+        if state.version.update(items, statistic) {
+            state.current = vec![0; items.len()];
+        }
+
+        // the running weights can't overflow with i128
+        let mut total = 0i128;
+        let mut best = None;
+        for (index, weight) in Scale::weights(extensions, items, weights(&*self.weight, items))
+            .into_iter()
+            .enumerate()
+        {
+            let weight = weight as i128;
+            if weight == 0 {
+                continue;
+            }
+            state.current[index] += weight;
+            total += weight;
+            if best
+                .filter(|best| state.current[*best] >= state.current[index])
+                .is_none()
+            {
+                best = Some(index);
+            }
+        }
+        if let Some(best) = best {
+            state.current[best] -= total;
+        }
+        Ok(best)
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(SmoothWeight::new(self.weight.clone())))
+    }
+}
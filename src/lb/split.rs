@@ -0,0 +1,187 @@
+use crate::lb::hash::{hash, HashKey};
+use crate::lb::policy::{sealed, LoadBalancerPolicy, LoadBalancerPolicyTrait, Subsets};
+use crate::lb::weight::{choose_weighted, pick_weighted};
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+struct Group<I> {
+    weight: AtomicUsize,
+    predicate: Box<dyn Fn(&I) -> bool + Send + Sync>,
+}
+
+///
+/// Split the traffic between the element groups by the weights, e.g. 95% stable and 5% canary.
+/// The element belongs to the first group which the predicate matches, and the element not
+/// belongs to any group will never be chosen. The group will be chosen by the [`HashKey`] in the
+/// extensions if it exists, so the same key always goes to the same group, then the policy
+/// chooses the element in the group, each group has its own state of the policy.
+///
+/// The split is a handle built by [`SplitBuilder`], the groups are frozen at the build, and the
+/// weights can be adjusted at runtime by the clone of it.
+///
+/// ```
+/// use reqwest_lb::{LoadBalancerPolicy, Split};
+///
+/// let split = Split::builder(LoadBalancerPolicy::RoundRobin)
+///     .group(95, |port: &u16| *port != 3000)
+///     .group(5, |port: &u16| *port == 3000)
+///     .build();
+/// let policy = LoadBalancerPolicy::split(split.clone());
+///
+/// // move 10% traffic to the canary group
+/// split.set_weight(0, 90);
+/// split.set_weight(1, 10);
+/// ```
+///
+pub struct Split<I> {
+    groups: Arc<Vec<Group<I>>>,
+    subsets: Arc<Subsets<usize, I>>,
+}
+
+impl<I> Clone for Split<I> {
+    fn clone(&self) -> Self {
+        Self {
+            groups: self.groups.clone(),
+            subsets: self.subsets.clone(),
+        }
+    }
+}
+
+///
+/// The builder of the [`Split`], the groups are added before the split is built
+///
+pub struct SplitBuilder<I> {
+    groups: Vec<Group<I>>,
+    policy: LoadBalancerPolicy<I>,
+}
+
+impl<I: Clone + 'static> SplitBuilder<I> {
+    ///
+    /// Add a group with the weight
+    ///
+    pub fn group<F>(mut self, weight: usize, predicate: F) -> Self
+    where
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        self.groups.push(Group {
+            weight: AtomicUsize::new(weight),
+            predicate: Box::new(predicate),
+        });
+        self
+    }
+
+    pub fn build(self) -> Split<I> {
+        Split {
+            groups: Arc::new(self.groups),
+            subsets: Arc::new(Subsets::new(self.policy)),
+        }
+    }
+}
+
+impl<I> Split<I> {
+    pub fn builder(policy: LoadBalancerPolicy<I>) -> SplitBuilder<I> {
+        SplitBuilder {
+            groups: Vec::new(),
+            policy,
+        }
+    }
+
+    ///
+    /// Adjust the weight of the group at the index
+    ///
+    pub fn set_weight(&self, index: usize, weight: usize) {
+        if let Some(group) = self.groups.get(index) {
+            group.weight.store(weight, Ordering::Relaxed);
+        }
+    }
+
+    pub fn weights(&self) -> Vec<usize> {
+        self.groups
+            .iter()
+            .map(|group| group.weight.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    ///
+    /// The fork of the policy in the group of the element
+    ///
+    fn policy(&self, item: &I) -> Option<LoadBalancerPolicy<I>>
+    where
+        I: Clone + 'static,
+    {
+        let group = self.groups.iter().position(|g| (g.predicate)(item))?;
+        self.subsets.get(&group)
+    }
+}
+
+impl<I> sealed::Sealed<I> for Split<I> {}
+
+impl<I> LoadBalancerPolicyTrait<I> for Split<I>
+where
+    I: Clone + Send + Sync + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let mut subsets = vec![Vec::new(); self.groups.len()];
+        for (index, item) in items.iter().enumerate() {
+            if let Some(group) = self.groups.iter().position(|g| (g.predicate)(item)) {
+                subsets[group].push(index);
+            }
+        }
+
+        // the empty group takes no traffic
+        let weights = self
+            .groups
+            .iter()
+            .zip(subsets.iter())
+            .map(|(group, subset)| {
+                if subset.is_empty() {
+                    0
+                } else {
+                    group.weight.load(Ordering::Relaxed)
+                }
+            });
+        let group = match extensions.get::<HashKey>() {
+            Some(key) => pick_weighted(weights, |total| hash(&(key, "split")) as u128 % total),
+            None => statistic.with_rng(|rng| choose_weighted(weights, rng)),
+        };
+        let Some(group) = group else {
+            return Ok(None);
+        };
+        self.subsets
+            .choose(group, items, &subsets[group], statistic, extensions)
+    }
+
+    fn start(&self, item: &I, route: &Route) {
+        if let Some(policy) = self.policy(item) {
+            policy.start(item, route)
+        }
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, route: &Route) {
+        if let Some(policy) = self.policy(item) {
+            policy.finish(item, elapsed, route)
+        }
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, route: &Route) {
+        if let Some(policy) = self.policy(item) {
+            policy.record(item, outcome, route)
+        }
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        // share the weights, so the handle can still adjust them
+        Some(Arc::new(Split {
+            groups: self.groups.clone(),
+            subsets: Arc::new(Subsets::new(self.subsets.policy().clone())),
+        }))
+    }
+}
@@ -0,0 +1,166 @@
+use crate::lb::hash::Version;
+use crate::lb::policy::{sealed, LoadBalancerPolicyTrait};
+use crate::lb::{OutOfRange, Outcome, Route, Statistic};
+use http::Extensions;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+///
+/// The user defined policy with its own state, each load balancer holds a separate state which
+/// is created by `Default`, and the policy will be notified when the elements added or removed.
+///
+/// ```rust
+/// use http::Extensions;
+/// use reqwest_lb::{LoadBalancerPolicy, StatefulPolicy};
+///
+/// // choose the element which is chosen the fewest times
+/// struct Fewest;
+///
+/// impl StatefulPolicy<usize> for Fewest {
+///     type State = std::collections::HashMap<usize, u64>;
+///
+///     fn choose(&self, items: &[usize], _: &Extensions, state: &mut Self::State) -> Option<usize> {
+///         let index = (0..items.len()).min_by_key(|index| state.get(&items[*index]))?;
+///         *state.entry(items[index]).or_default() += 1;
+///         Some(index)
+///     }
+///
+///     fn removed(&self, item: &usize, state: &mut Self::State) {
+///         state.remove(item);
+///     }
+/// }
+///
+/// let policy = LoadBalancerPolicy::stateful(Fewest);
+/// ```
+///
+pub trait StatefulPolicy<I> {
+    ///
+    /// The state of the policy in a load balancer
+    ///
+    type State: Default + Send;
+
+    ///
+    /// choose the index of the element, return `None` if no element can be chosen
+    ///
+    fn choose(
+        &self,
+        items: &[I],
+        extensions: &Extensions,
+        state: &mut Self::State,
+    ) -> Option<usize>;
+
+    ///
+    /// the element appeared in the items, it's notified before choose
+    ///
+    fn added(&self, _item: &I, _state: &mut Self::State) {}
+
+    ///
+    /// the element disappeared from the items, it's notified before choose
+    ///
+    fn removed(&self, _item: &I, _state: &mut Self::State) {}
+
+    ///
+    /// a request to the chosen element started
+    ///
+    fn start(&self, _item: &I, _state: &mut Self::State) {}
+
+    ///
+    /// a request to the chosen element finished
+    ///
+    fn finish(&self, _item: &I, _elapsed: Duration, _state: &mut Self::State) {}
+
+    ///
+    /// the outcome of a request to the chosen element reported
+    ///
+    fn record(&self, _item: &I, _outcome: &Outcome, _state: &mut Self::State) {}
+}
+
+struct Inner<I, S> {
+    version: Version,
+    items: HashSet<I>,
+    state: S,
+}
+
+///
+/// Adapt the [`StatefulPolicy`] to the policy trait, the items are tracked to notify the
+/// elements added or removed, they are compared only when the version of the elements changed.
+///
+pub(crate) struct Stateful<P: StatefulPolicy<I>, I> {
+    policy: Arc<P>,
+    inner: Mutex<Inner<I, P::State>>,
+}
+
+impl<P: StatefulPolicy<I>, I> Stateful<P, I> {
+    pub fn new(policy: Arc<P>) -> Self {
+        Self {
+            policy,
+            inner: Mutex::new(Inner {
+                version: Version::default(),
+                items: HashSet::new(),
+                state: P::State::default(),
+            }),
+        }
+    }
+}
+
+impl<P: StatefulPolicy<I>, I> sealed::Sealed<I> for Stateful<P, I> {}
+
+impl<P, I> LoadBalancerPolicyTrait<I> for Stateful<P, I>
+where
+    P: StatefulPolicy<I> + Send + Sync + 'static,
+    I: Hash + Eq + Clone + Send + 'static,
+{
+    fn choose(
+        &self,
+        items: &[I],
+        statistic: &Statistic,
+        extensions: &mut Extensions,
+    ) -> Result<Option<usize>, OutOfRange> {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner {
+            version,
+            items: known,
+            state,
+        } = &mut *inner;
+
+        // notify the changes of the elements
+        if version.update(items, statistic) {
+            let current = items.iter().collect::<HashSet<_>>();
+            known.retain(|item| {
+                let retain = current.contains(item);
+                if !retain {
+                    self.policy.removed(item, state);
+                }
+                retain
+            });
+            for item in items {
+                if known.insert(item.clone()) {
+                    self.policy.added(item, state);
+                }
+            }
+        }
+
+        OutOfRange::check(self.policy.choose(items, extensions, state), items.len())
+    }
+
+    fn start(&self, item: &I, _: &Route) {
+        self.policy
+            .start(item, &mut self.inner.lock().unwrap().state)
+    }
+
+    fn finish(&self, item: &I, elapsed: Duration, _: &Route) {
+        self.policy
+            .finish(item, elapsed, &mut self.inner.lock().unwrap().state)
+    }
+
+    fn record(&self, item: &I, outcome: &Outcome, _: &Route) {
+        self.policy
+            .record(item, outcome, &mut self.inner.lock().unwrap().state)
+    }
+
+    fn fork(&self) -> Option<Arc<dyn LoadBalancerPolicyTrait<I> + Send + Sync>> {
+        Some(Arc::new(Stateful::new(self.policy.clone())))
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+///
+/// Track the in flight requests count of each element
+///
+pub(crate) struct InFlight<I> {
+    counts: Mutex<HashMap<I, usize>>,
+}
+
+impl<I> Default for InFlight<I> {
+    fn default() -> Self {
+        Self {
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<I> InFlight<I>
+where
+    I: Hash + Eq + Clone,
+{
+    pub fn get(&self, item: &I) -> usize {
+        let counts = self.counts.lock().unwrap();
+        counts.get(item).copied().unwrap_or_default()
+    }
+
+    pub fn increment(&self, item: &I) {
+        let mut counts = self.counts.lock().unwrap();
+        match counts.get_mut(item) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(item.clone(), 1);
+            }
+        }
+    }
+
+    pub fn decrement(&self, item: &I) {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(item) {
+            *count = count.saturating_sub(1);
+            // remove the idle element, so the removed element will not leak
+            if *count == 0 {
+                counts.remove(item);
+            }
+        }
+    }
+}
@@ -1,9 +1,12 @@
-pub trait WeightProvider<I>: sealed::Sealed<I> {
+use rand::Rng;
+
+///
+/// Provide the weight of the element, it can be implemented for the user defined types
+///
+pub trait WeightProvider<I> {
     fn weight(&self, item: &I) -> usize;
 }
 
-impl<I, F> sealed::Sealed<I> for F where F: Fn(&I) -> usize {}
-
 impl<I, F> WeightProvider<I> for F
 where
     F: Fn(&I) -> usize,
@@ -13,6 +16,76 @@ where
     }
 }
 
-mod sealed {
-    pub trait Sealed<I> {}
+///
+/// The weights of the elements, the elements weigh the same if all the weights are zero, so they
+/// are still reachable rather than not found
+///
+pub(crate) fn weights<I, W>(provider: &W, items: &[I]) -> Vec<usize>
+where
+    W: WeightProvider<I> + ?Sized,
+{
+    let weights = items
+        .iter()
+        .map(|item| provider.weight(item))
+        .collect::<Vec<_>>();
+    if weights.iter().all(|weight| *weight == 0) {
+        vec![1; weights.len()]
+    } else {
+        weights
+    }
+}
+
+///
+/// Choose an index randomly in proportion to the weights, the zero weight will never be chosen,
+/// return `None` if all the weights are zero.
+///
+pub(crate) fn choose_weighted<W, R>(weights: W, rng: &mut R) -> Option<usize>
+where
+    W: IntoIterator<Item = usize>,
+    R: Rng + ?Sized,
+{
+    pick_weighted(weights, |total| rng.gen_range(0..total))
+}
+
+///
+/// The fraction of the golden ratio in the 64 bits fixed point
+///
+const GOLDEN: u64 = 0x9E37_79B9_7F4A_7C15;
+
+///
+/// Choose the index by the weights in the order of the cursor, the points of the consecutive
+/// cursors follow the golden ratio sequence, so the picks of an element are spread evenly rather
+/// than in a run
+///
+pub(crate) fn choose_cursor<W>(weights: W, cursor: u64) -> Option<usize>
+where
+    W: IntoIterator<Item = usize>,
+{
+    pick_weighted(weights, |total| {
+        (cursor.wrapping_mul(GOLDEN) as u128 * total) >> u64::BITS
+    })
+}
+
+///
+/// Pick the index which the point (in the range `0..total`) falls in the cumulative weights
+///
+pub(crate) fn pick_weighted<W, P>(weights: W, point: P) -> Option<usize>
+where
+    W: IntoIterator<Item = usize>,
+    P: FnOnce(u128) -> u128,
+{
+    // the cumulative weights can't overflow with u128
+    let mut total = 0u128;
+    let cumulative = weights
+        .into_iter()
+        .map(|weight| {
+            total += weight as u128;
+            total
+        })
+        .collect::<Vec<_>>();
+    if total == 0 {
+        return None;
+    }
+    let point = point(total);
+    Some(cumulative.partition_point(|weight| *weight <= point))
 }
@@ -1,10 +1,15 @@
-use crate::lb::LoadBalancerRegistry;
+use crate::lb::hash::hash;
+use crate::lb::{Affinity, ChooseError, Feedback, LoadBalancerRegistry, OutOfRange};
 use crate::BoxError;
 use async_trait::async_trait;
-use http::Extensions;
+use http::header::{COOKIE, SET_COOKIE};
+use http::{Extensions, HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Request, Response, Url};
 use reqwest_middleware::{Middleware, Next};
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use tracing::debug;
 
@@ -17,18 +22,143 @@ fn is_lb_schema(schema: &str) -> bool {
 
 pub struct LoadBalancerMiddleware<I, E> {
     registry: LoadBalancerRegistry<I, E>,
+    sticky: Option<Sticky>,
+    load_header: Option<HeaderName>,
+}
+
+///
+/// The max count of the cached position hints of the sticky session tokens
+///
+const MAX_STICKY_HINTS: usize = 1024;
+
+///
+/// The max count of the sticky sessions of the [`StickySession`] keys
+///
+const MAX_STICKY_SESSIONS: usize = 65536;
+
+///
+/// The session key of the caller (e.g. the user id) in the request extensions, the requests of
+/// the same key to the same `lb://` host go back to the element served the last one, without
+/// forwarding the cookie.
+///
+/// ```
+/// use reqwest_lb::StickySession;
+///
+/// # async fn run(client: reqwest_middleware::ClientWithMiddleware) {
+/// let response = client
+///     .get("lb://example-server/")
+///     .with_extension(StickySession::new("user-1"))
+///     .send()
+///     .await;
+/// # }
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StickySession(String);
+
+impl StickySession {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+}
+
+///
+/// The sticky session state. The reqwest cookie store is applied after the middleware, and it
+/// keeps the cookie under the origin of the element rather than the `lb://` host, so the
+/// request is pinned by the cookie in it, or by the session of the [`StickySession`] key which is
+/// kept by the middleware.
+///
+struct Sticky {
+    cookie: String,
+
+    ///
+    /// the token of the element served the last request of the load balancer host and the
+    /// session key
+    ///
+    sessions: Mutex<HashMap<(String, StickySession), String>>,
+
+    ///
+    /// the position hint of the element of the token, so the pinned request converts the
+    /// element at the hint to the url rather than each of them
+    ///
+    hints: Mutex<HashMap<String, Arc<AtomicUsize>>>,
 }
 
 impl<I, E> LoadBalancerMiddleware<I, E> {
     pub fn new(registry: LoadBalancerRegistry<I, E>) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            sticky: None,
+            load_header: None,
+        }
+    }
+
+    ///
+    /// Enable the cookie based sticky session, the request with the cookie (e.g. forwarded from
+    /// the downstream client) goes to the element recorded in it while it is still supplied, and
+    /// the response will set the cookie when the element changed, so it can be forwarded back.
+    /// The request without the cookie is pinned by the [`StickySession`] key in the request
+    /// extensions, or it's not pinned.
+    ///
+    /// The [`Affinity`] inserted in the request extensions takes precedence over the session.
+    ///
+    pub fn sticky(mut self, cookie: impl Into<String>) -> Self {
+        self.sticky = Some(Sticky {
+            cookie: cookie.into(),
+            sessions: Mutex::new(HashMap::new()),
+            hints: Mutex::new(HashMap::new()),
+        });
+        self
     }
+
+    ///
+    /// Parse the load reported by the element in the response header, e.g. `X-Server-Load: 0.7`
+    /// or the ORCA text format `endpoint-load-metrics: TEXT cpu_utilization=0.7`, and report it
+    /// to the load balancer with the outcome.
+    ///
+    pub fn load_header(mut self, header: HeaderName) -> Self {
+        self.load_header = Some(header);
+        self
+    }
+}
+
+///
+/// The sticky session token of the element, it's the hash of the url origin
+///
+fn token(url: &Url) -> String {
+    format!("{:016x}", hash(&url.origin().ascii_serialization()))
+}
+
+///
+/// Parse the load as a number, or the `cpu_utilization` of the ORCA text format
+///
+fn parse_load(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value.parse().ok().or_else(|| {
+        value
+            .strip_prefix("TEXT")?
+            .split(',')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(key, _)| *key == "cpu_utilization")
+            .and_then(|(_, value)| value.trim().parse().ok())
+    })
+}
+
+fn find_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
 }
 
 #[async_trait]
 impl<I, E, IE> Middleware for LoadBalancerMiddleware<I, E>
 where
-    I: TryInto<Url, Error = IE> + 'static,
+    I: TryInto<Url, Error = IE> + Clone + Send + 'static,
     IE: Into<BoxError> + 'static,
     E: Into<BoxError> + 'static,
 {
@@ -45,16 +175,100 @@ where
                 .registry
                 .find(host)
                 .ok_or(Error::NotFoundLoadBalancer)?;
-            let item = load_balancer
-                .choose(extensions)
-                .await
-                .map_err(|e| Error::Customize(e.into()))?
+
+            // pin the request to the element recorded in the cookie or the session of the key,
+            // unless the request is pinned by the user
+            let sticky = self
+                .sticky
+                .as_ref()
+                .filter(|_| extensions.get::<Affinity<I>>().is_none());
+            let cookie = sticky.and_then(|sticky| {
+                find_cookie(request.headers(), &sticky.cookie).map(|token| token.to_string())
+            });
+            let session = sticky
+                .and_then(|_| extensions.get::<StickySession>())
+                .map(|key| (host.to_string(), key.clone()));
+            let pinned = sticky.and_then(|sticky| {
+                let pinned = match &session {
+                    Some(session) if cookie.is_none() => {
+                        sticky.sessions.lock().unwrap().get(session).cloned()
+                    }
+                    _ => cookie.clone(),
+                }?;
+                let mut hints = sticky.hints.lock().unwrap();
+                if hints.len() >= MAX_STICKY_HINTS && !hints.contains_key(&pinned) {
+                    hints.clear();
+                }
+                let hint = hints.entry(pinned.clone()).or_default().clone();
+                Some((pinned, hint))
+            });
+            let is_pinned = pinned.is_some();
+            if let Some((pinned, hint)) = pinned {
+                let predicate = move |item: &I| {
+                    item.clone()
+                        .try_into()
+                        .is_ok_and(|url| token(&url) == pinned)
+                };
+                extensions.insert(Affinity::with_hint(predicate, hint));
+            }
+            let item = load_balancer.choose(extensions).await;
+            if is_pinned {
+                extensions.remove::<Affinity<I>>();
+            }
+            let item = item
+                .map_err(|e| match e {
+                    ChooseError::Supplier(e) => Error::Customize(e.into()),
+                    ChooseError::OutOfRange(e) => Error::OutOfRange(e),
+                })?
                 .ok_or(Error::NotFoundElement)?;
+
+            // keep the element in flight until the request finished, and report the outcome
+            let mut feedback = Feedback::new(&**load_balancer, item.clone(), extensions);
             let source = request.url();
             let mut target = item.try_into().map_err(|e| Error::InvalidUrl(e.into()))?;
+            let served = token(&target);
+            if let Some((sticky, session)) = sticky.zip(session) {
+                let mut sessions = sticky.sessions.lock().unwrap();
+                if sessions.len() >= MAX_STICKY_SESSIONS && !sessions.contains_key(&session) {
+                    sessions.clear();
+                }
+                sessions.insert(session, served.clone());
+            }
             reconstruct(source, &mut target);
             debug!("reconstruct new url: {}", target.as_str());
             *request.url_mut() = target;
+            let response = next.run(request, extensions).await;
+            let load = self.load_header.as_ref().and_then(|header| {
+                let response = response.as_ref().ok()?;
+                parse_load(response.headers().get(header)?.to_str().ok()?)
+            });
+            if let Some(load) = load {
+                feedback.report_load(load);
+            }
+            let mut response = match response {
+                Ok(response) if response.status().is_server_error() => {
+                    feedback.record_failure(Some(response.status()));
+                    response
+                }
+                Ok(response) => {
+                    feedback.record_success(response.status());
+                    response
+                }
+                Err(e) => {
+                    feedback.record_failure(e.status());
+                    return Err(e);
+                }
+            };
+
+            // record the element served the request when it changed
+            if let Some(sticky) = sticky {
+                if cookie.as_ref() != Some(&served) {
+                    let value = format!("{}={}; Path=/", sticky.cookie, served);
+                    let value = HeaderValue::from_str(&value).map_err(Error::customize)?;
+                    response.headers_mut().append(SET_COOKIE, value);
+                }
+            }
+            return Ok(response);
         }
         next.run(request, extensions).await
     }
@@ -77,6 +291,9 @@ pub enum Error {
     #[error("Load balancer not found element")]
     NotFoundElement,
 
+    #[error(transparent)]
+    OutOfRange(#[from] OutOfRange),
+
     #[error("Request miss host")]
     MissHost,
 
@@ -8,8 +8,9 @@ use std::fmt::Debug;
 use std::future::poll_fn;
 use std::hash::Hash;
 use std::pin::pin;
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::spawn;
 use tokio::sync::{Notify, RwLock};
 use tracing::{error, info};
@@ -32,6 +33,8 @@ const STATE_INITIALIZED: u8 = 2;
 struct Shared<D: Discovery> {
     state: AtomicU8,
     elements: RwLock<HashMap<D::Key, D::Element>>,
+    generation: Arc<AtomicU64>,
+    inserted: std::sync::RwLock<HashMap<D::Key, Instant>>,
     notify: Notify,
 }
 
@@ -40,6 +43,8 @@ impl<D: Discovery> Shared<D> {
         Self {
             state: AtomicU8::new(STATE_NEW),
             elements: RwLock::new(HashMap::new()),
+            generation: Arc::default(),
+            inserted: std::sync::RwLock::new(HashMap::new()),
             notify: Notify::new(),
         }
     }
@@ -60,7 +65,7 @@ impl<D: Discovery> Clone for DiscoverySupplier<D> {
 impl<D> DiscoverySupplier<D>
 where
     D: Discovery + Send + 'static,
-    D::Key: Eq + Hash + Send + Sync + 'static,
+    D::Key: Eq + Hash + Clone + Send + Sync + 'static,
     D::Element: Send + Sync + 'static,
     D::Error: Debug + Send,
 {
@@ -70,6 +75,41 @@ where
         Self { shared }
     }
 
+    ///
+    /// The instant the element of the key was inserted, `None` if it's an initial element (inserted
+    /// before the discovery initialized) or it not exists
+    ///
+    pub fn inserted(&self, key: &D::Key) -> Option<Instant> {
+        self.shared.inserted.read().unwrap().get(key).copied()
+    }
+
+    ///
+    /// The [`inserted`](Self::inserted) instant of the element, the key of the element is
+    /// extracted by the `key`, e.g. the source of the [`SlowStart::since`](crate::SlowStart::since)
+    ///
+    /// ```no_run
+    /// use reqwest_lb::discovery::Change;
+    /// use reqwest_lb::supplier::DiscoverySupplier;
+    /// use reqwest_lb::{LoadBalancerPolicy, SlowStart};
+    /// use std::convert::Infallible;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() {
+    /// let changes = vec![Ok::<_, Infallible>(Change::Insert(3001u16, 3001u16))];
+    /// let supplier = DiscoverySupplier::new(futures::stream::iter(changes));
+    /// let slow_start = SlowStart::new(LoadBalancerPolicy::RoundRobin, Duration::from_secs(60))
+    ///     .since(supplier.inserted_by(|port: &u16| *port));
+    /// # }
+    /// ```
+    ///
+    pub fn inserted_by<F>(&self, key: F) -> impl Fn(&D::Element) -> Option<Instant> + Send + Sync
+    where
+        F: Fn(&D::Element) -> D::Key + Send + Sync,
+    {
+        let shared = self.shared.clone();
+        move |element| shared.inserted.read().unwrap().get(&key(element)).copied()
+    }
+
     fn try_upgrade_state(state: &AtomicU8, old_state: u8, new_state: u8) -> bool {
         state
             .compare_exchange(old_state, new_state, Ordering::SeqCst, Ordering::SeqCst)
@@ -86,12 +126,23 @@ where
                             Change::Insert(k, v) => {
                                 info!("Collector receive insert change: key={:?}", k);
                                 let mut items = shared.elements.write().await;
+                                // the update of the element is not a new element
+                                if !items.contains_key(&k)
+                                    && shared.state.load(Ordering::SeqCst) == STATE_INITIALIZED
+                                {
+                                    let mut inserted = shared.inserted.write().unwrap();
+                                    inserted.insert(k.clone(), Instant::now());
+                                }
                                 items.insert(k, v);
+                                shared.generation.fetch_add(1, Ordering::SeqCst);
                             }
                             Change::Remove(k) => {
                                 info!("Collector receive remove change: key={:?}", k);
                                 let mut items = shared.elements.write().await;
-                                items.remove(&k);
+                                if items.remove(&k).is_some() {
+                                    shared.generation.fetch_add(1, Ordering::SeqCst);
+                                }
+                                shared.inserted.write().unwrap().remove(&k);
                             }
                             Change::Initialized => {
                                 if Self::try_upgrade_state(
@@ -138,4 +189,10 @@ where
             Ok(elements.into_iter().map(|(_, v)| v).collect())
         })
     }
+
+    fn generation(&self) -> Option<Arc<AtomicU64>> {
+        // it's increased with the elements locked, so the elements read between the same
+        // generation are the same
+        Some(self.shared.generation.clone())
+    }
 }
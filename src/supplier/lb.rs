@@ -1,56 +1,158 @@
-use crate::lb::{LoadBalancerPolicy, LoadBalancerPolicyTrait, Statistic};
+use crate::lb::{
+    Affinity, AsyncLoadBalancerPolicyTrait, ChooseError, LoadBalancerPolicy,
+    LoadBalancerPolicyTrait, OutOfRange, Outcome, Route, Statistic,
+};
 use crate::supplier::Supplier;
 use crate::LoadBalancerTrait;
+use futures::future::BoxFuture;
 use http::Extensions;
 use pin_project_lite::pin_project;
+use rand::RngCore;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{ready, Context, Poll};
+use std::time::Duration;
 
 pub struct LoadBalancer<S: Supplier> {
     supplier: S,
-    policy: LoadBalancerPolicy<S::Element>,
+    policy: Policy<S::Element>,
     statistic: Statistic,
 }
 
+///
+/// The policy of the load balancer, the async policy is driven by the choose future
+///
+enum Policy<I> {
+    Sync(LoadBalancerPolicy<I>),
+    Async(Arc<dyn AsyncLoadBalancerPolicyTrait<I> + Send + Sync>),
+}
+
+impl<I> Clone for Policy<I> {
+    fn clone(&self) -> Self {
+        match self {
+            Policy::Sync(policy) => Policy::Sync(policy.clone()),
+            Policy::Async(policy) => Policy::Async(policy.clone()),
+        }
+    }
+}
+
 impl<S: Supplier> LoadBalancer<S> {
     pub fn new(supplier: S, policy: LoadBalancerPolicy<S::Element>) -> Self {
         Self {
             supplier,
-            policy,
+            policy: Policy::Sync(policy.fresh()),
             statistic: Statistic::default(),
         }
     }
+
+    ///
+    /// Choose with the async policy, e.g. awaiting a lock or an async lookup. The future owns the
+    /// supplied elements and resolves the chosen one.
+    ///
+    /// ```rust
+    /// use reqwest_lb::supplier::LoadBalancer;
+    ///
+    /// let load_balancer = LoadBalancer::with_async_policy(vec![1, 2, 3], |items: Vec<i32>, _| {
+    ///     async move { items.into_iter().max() }
+    /// });
+    /// ```
+    ///
+    pub fn with_async_policy<P>(supplier: S, policy: P) -> Self
+    where
+        P: AsyncLoadBalancerPolicyTrait<S::Element> + Send + Sync + 'static,
+    {
+        Self {
+            supplier,
+            policy: Policy::Async(Arc::new(policy)),
+            statistic: Statistic::default(),
+        }
+    }
+
+    ///
+    /// Use the (seeded) rng for the random choices of the policy, the same seed gives the
+    /// identical choice sequence, the thread rng will be used by default.
+    ///
+    pub fn rng<R: RngCore + Send + 'static>(mut self, rng: R) -> Self {
+        self.statistic.rng = Some(Arc::new(Mutex::new(Box::new(rng))));
+        self
+    }
+
+    ///
+    /// The round robin cursor, it's the count of the chosen times
+    ///
+    pub fn cursor(&self) -> u64 {
+        self.statistic.count.load(Ordering::SeqCst)
+    }
+
+    ///
+    /// Reset the round robin cursor, the next round robin choose will start from it
+    ///
+    pub fn set_cursor(&self, cursor: u64) {
+        self.statistic.count.store(cursor, Ordering::SeqCst);
+    }
 }
 
 impl<S> LoadBalancerTrait for LoadBalancer<S>
 where
     S: Supplier,
+    S::Element: 'static,
 {
     type Element = S::Element;
     type Error = S::Error;
     type Future = ChooseFuture<S::Element, S::Future>;
 
     fn choose(&self, extensions: &mut Extensions) -> Self::Future {
-        // touch statistic
-        self.statistic.count.fetch_add(1, Ordering::SeqCst);
-        extensions.insert(self.statistic.clone());
+        // touch statistic, keep the taken count in the future
+        let mut statistic = self.statistic.clone();
+        statistic.cursor = self.statistic.count.fetch_add(1, Ordering::SeqCst);
+        extensions.insert(statistic.clone());
+        // a new route for the request, the hooks of it go along the route
+        extensions.insert(Route::default());
         let extensions = extensions.clone();
+        let generation = self.supplier.generation().map(|generation| {
+            let current = generation.load(Ordering::SeqCst);
+            (generation, current)
+        });
         let future = self.supplier.get();
         let policy = self.policy.clone();
         ChooseFuture {
             extensions,
             policy,
+            statistic,
+            generation,
+            pending: None,
             future,
         }
     }
+
+    fn start(&self, element: &Self::Element, route: &Route) {
+        if let Policy::Sync(policy) = &self.policy {
+            policy.start(element, route)
+        }
+    }
+
+    fn finish(&self, element: &Self::Element, elapsed: Duration, route: &Route) {
+        if let Policy::Sync(policy) = &self.policy {
+            policy.finish(element, elapsed, route)
+        }
+    }
+
+    fn record(&self, element: &Self::Element, outcome: &Outcome, route: &Route) {
+        if let Policy::Sync(policy) = &self.policy {
+            policy.record(element, outcome, route)
+        }
+    }
 }
 
 pin_project! {
     pub struct ChooseFuture<I, F> {
         extensions: Extensions,
-        policy: LoadBalancerPolicy<I>,
+        policy: Policy<I>,
+        statistic: Statistic,
+        generation: Option<(Arc<AtomicU64>, u64)>,
+        pending: Option<BoxFuture<'static, Option<I>>>,
         #[pin]
         future: F,
     }
@@ -58,26 +160,66 @@ pin_project! {
 
 impl<I, E, F> Future for ChooseFuture<I, F>
 where
+    I: 'static,
     F: Future<Output = Result<Vec<I>, E>>,
 {
-    type Output = Result<Option<I>, E>;
+    type Output = Result<Option<I>, ChooseError<E>>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let project = self.project();
+
+        // drive the async policy
+        if let Some(pending) = project.pending.as_mut() {
+            let element = ready!(pending.as_mut().poll(cx));
+            *project.pending = None;
+            return Poll::Ready(Ok(element));
+        }
+
         match ready!(project.future.poll(cx)) {
             Ok(mut elements) => {
+                // the generation is known only if it's not changed during the get
+                project.statistic.generation =
+                    project.generation.take().and_then(|(generation, before)| {
+                        (generation.load(Ordering::SeqCst) == before).then_some(before)
+                    });
                 let size = elements.len();
                 Poll::Ready(match size {
                     0 => Ok(None),
                     1 => Ok(Some(elements.remove(0))),
                     _ => {
+                        // the pinned element has priority over the policy
+                        let pinned = project
+                            .extensions
+                            .get::<Affinity<I>>()
+                            .and_then(|affinity| affinity.position(&elements));
                         // use policy choose and return the index
-                        let index = project.policy.choose(&elements, project.extensions);
-                        Ok(Some(elements.remove(index)))
+                        let index = match (pinned, &*project.policy) {
+                            (Some(index), _) => Ok(Some(index)),
+                            (None, Policy::Sync(policy)) => {
+                                policy.choose(&elements, project.statistic, project.extensions)
+                            }
+                            (None, Policy::Async(policy)) => {
+                                let extensions = project.extensions.clone();
+                                *project.pending = Some(policy.choose(elements, extensions));
+                                cx.waker().wake_by_ref();
+                                return Poll::Pending;
+                            }
+                        };
+                        index
+                            .map_err(ChooseError::from)
+                            .and_then(|index| take(elements, index))
                     }
                 })
             }
-            Err(e) => Poll::Ready(Err(e)),
+            Err(e) => Poll::Ready(Err(ChooseError::Supplier(e))),
         }
     }
 }
+
+///
+/// Take the chosen element, the index out of range is an error rather than a panic
+///
+fn take<I, E>(mut elements: Vec<I>, index: Option<usize>) -> Result<Option<I>, ChooseError<E>> {
+    let index = OutOfRange::check(index, elements.len())?;
+    Ok(index.map(|index| elements.remove(index)))
+}
@@ -1,11 +1,15 @@
-pub mod iter;
 mod discovery;
+pub mod iter;
 mod lb;
+mod subset;
 
 pub use discovery::*;
 pub use lb::*;
+pub use subset::*;
 
 use std::future::Future;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
 
 pub trait Supplier {
     ///
@@ -27,4 +31,13 @@ pub trait Supplier {
     /// Get current all elements
     ///
     fn get(&self) -> Self::Future;
+
+    ///
+    /// The generation of the elements, it must be increased when the elements changed, so the
+    /// policies can cache the state of the elements (e.g. the lookup table) without checking
+    /// them on every choose. Return `None` if it's unknown, then the elements will be checked.
+    ///
+    fn generation(&self) -> Option<Arc<AtomicU64>> {
+        None
+    }
 }
@@ -0,0 +1,181 @@
+use crate::supplier::Supplier;
+use pin_project_lite::pin_project;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use thiserror::Error;
+
+///
+/// The element with the labels (metadata), used to select the subset
+///
+pub trait Labeled {
+    fn label(&self, key: &str) -> Option<&str>;
+}
+
+impl<S: BuildHasher> Labeled for HashMap<String, String, S> {
+    fn label(&self, key: &str) -> Option<&str> {
+        self.get(key).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+    NotExists(String),
+}
+
+impl Requirement {
+    fn matches<L: Labeled>(&self, element: &L) -> bool {
+        match self {
+            Requirement::Equals(key, value) => element.label(key) == Some(value.as_str()),
+            Requirement::NotEquals(key, value) => element.label(key) != Some(value.as_str()),
+            Requirement::Exists(key) => element.label(key).is_some(),
+            Requirement::NotExists(key) => element.label(key).is_none(),
+        }
+    }
+}
+
+///
+/// The label selector, the requirements are separated by comma and all of them must be matched.
+///
+/// - `key=value` or `key==value`: the label equals the value
+/// - `key!=value`: the label not equals the value (or not exists)
+/// - `key`: the label exists
+/// - `!key`: the label not exists
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selector {
+    requirements: Vec<Requirement>,
+}
+
+impl Selector {
+    pub fn matches<L: Labeled>(&self, element: &L) -> bool {
+        self.requirements.iter().all(|r| r.matches(element))
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid selector requirement: {0}")]
+pub struct SelectorError(String);
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_key = |key: &str| {
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| !c.is_whitespace() && c != '=' && c != '!')
+        };
+        let mut requirements = Vec::new();
+        for requirement in s.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let invalid = || SelectorError(requirement.to_string());
+            let parsed = if let Some((key, value)) = requirement.split_once("!=") {
+                Requirement::NotEquals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some((key, value)) = requirement
+                .split_once("==")
+                .or_else(|| requirement.split_once('='))
+            {
+                Requirement::Equals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some(key) = requirement.strip_prefix('!') {
+                Requirement::NotExists(key.trim().to_string())
+            } else {
+                Requirement::Exists(requirement.to_string())
+            };
+            let key = match &parsed {
+                Requirement::Equals(key, _)
+                | Requirement::NotEquals(key, _)
+                | Requirement::Exists(key)
+                | Requirement::NotExists(key) => key,
+            };
+            if !is_key(key) {
+                return Err(invalid());
+            }
+            requirements.push(parsed);
+        }
+        Ok(Self { requirements })
+    }
+}
+
+///
+/// Select the subset of the supplier elements by the label selector. The subset is evaluated on
+/// every get, so it follows the changes of the supplier, and many subsets can share one (cloned)
+/// [`DiscoverySupplier`](crate::supplier::DiscoverySupplier) without another discovery task.
+///
+pub struct SubsetSupplier<S> {
+    supplier: S,
+    selector: Arc<Selector>,
+}
+
+impl<S: Clone> Clone for SubsetSupplier<S> {
+    fn clone(&self) -> Self {
+        Self {
+            supplier: self.supplier.clone(),
+            selector: self.selector.clone(),
+        }
+    }
+}
+
+impl<S> SubsetSupplier<S> {
+    pub fn new(supplier: S, selector: Selector) -> Self {
+        Self {
+            supplier,
+            selector: Arc::new(selector),
+        }
+    }
+}
+
+impl<S> Supplier for SubsetSupplier<S>
+where
+    S: Supplier,
+    S::Element: Labeled,
+{
+    type Element = S::Element;
+    type Error = S::Error;
+    type Future = SubsetFuture<S::Future>;
+
+    fn get(&self) -> Self::Future {
+        SubsetFuture {
+            selector: self.selector.clone(),
+            future: self.supplier.get(),
+        }
+    }
+
+    fn generation(&self) -> Option<Arc<AtomicU64>> {
+        // the subset of the same elements is the same
+        self.supplier.generation()
+    }
+}
+
+pin_project! {
+    pub struct SubsetFuture<F> {
+        selector: Arc<Selector>,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<I, E, F> Future for SubsetFuture<F>
+where
+    I: Labeled,
+    F: Future<Output = Result<Vec<I>, E>>,
+{
+    type Output = Result<Vec<I>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let project = self.project();
+        let elements = ready!(project.future.poll(cx))?;
+        Poll::Ready(Ok(elements
+            .into_iter()
+            .filter(|element| project.selector.matches(element))
+            .collect()))
+    }
+}
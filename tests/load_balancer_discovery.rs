@@ -1,8 +1,10 @@
 use http::Extensions;
 use reqwest::Url;
 use reqwest_lb::discovery::Change;
-use reqwest_lb::supplier::{DiscoverySupplier, LoadBalancer};
-use reqwest_lb::{LoadBalancerPolicy, LoadBalancerTrait};
+use reqwest_lb::supplier::{DiscoverySupplier, LoadBalancer, Supplier};
+use reqwest_lb::{HashKey, LoadBalancerPolicy, LoadBalancerTrait};
+use std::convert::Infallible;
+use std::sync::atomic::Ordering;
 
 #[tokio::test]
 async fn load_balancer_discovery() {
@@ -30,3 +32,48 @@ async fn load_balancer_discovery() {
         );
     }
 }
+
+#[tokio::test]
+async fn load_balancer_discovery_generation() {
+    let url = |port: u16| Url::parse(&format!("http://127.0.0.1:{}", port)).unwrap();
+    let (sender, receiver) = futures::channel::mpsc::unbounded::<Result<_, Infallible>>();
+    for port in [3000, 3001, 3002] {
+        sender
+            .unbounded_send(Ok(Change::Insert(port, url(port))))
+            .unwrap();
+    }
+    sender.unbounded_send(Ok(Change::Initialized)).unwrap();
+
+    let supplier = DiscoverySupplier::new(receiver);
+    let load_balancer = LoadBalancer::new(supplier.clone(), LoadBalancerPolicy::maglev(65537));
+    let mut extensions = Extensions::new();
+    extensions.insert(HashKey::new(&7));
+    let selected = load_balancer
+        .choose(&mut extensions)
+        .await
+        .unwrap()
+        .unwrap();
+    let generation = supplier.generation().unwrap();
+    assert_eq!(generation.load(Ordering::SeqCst), 3);
+
+    // the lookup table is rebuilt when the generation changed
+    let port = selected.port().unwrap();
+    sender.unbounded_send(Ok(Change::Remove(port))).unwrap();
+    while generation.load(Ordering::SeqCst) == 3 {
+        tokio::task::yield_now().await;
+    }
+    let selected = load_balancer
+        .choose(&mut extensions)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(selected.port(), Some(port));
+
+    // the instant of the element inserted after the discovery initialized is recorded
+    assert_eq!(supplier.inserted(&3000), None);
+    sender.unbounded_send(Ok(Change::Insert(3003, url(3003)))).unwrap();
+    while generation.load(Ordering::SeqCst) == 4 {
+        tokio::task::yield_now().await;
+    }
+    assert!(supplier.inserted(&3003).is_some());
+}
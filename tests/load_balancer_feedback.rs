@@ -0,0 +1,132 @@
+use http::{Extensions, HeaderName, StatusCode};
+use reqwest::{Client, Url};
+use reqwest_lb::supplier::LoadBalancer;
+use reqwest_lb::{
+    LoadBalancerMiddleware, LoadBalancerPolicy, LoadBalancerRegistry, Outcome, StatefulPolicy,
+};
+use reqwest_middleware::ClientBuilder;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+///
+/// start a http server which responds the status and the load metrics header
+///
+async fn serve_load(status: u16, load: &'static str) -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 {} Status\r\nEndpoint-Load-Metrics: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status, load
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+    Url::parse(&format!("http://127.0.0.1:{}", port)).unwrap()
+}
+
+async fn serve(status: u16) -> Url {
+    serve_load(status, "0").await
+}
+
+///
+/// record the reported outcomes
+///
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(Url, Outcome)>>>);
+
+impl StatefulPolicy<Url> for Recorder {
+    type State = ();
+
+    fn choose(&self, _: &[Url], _: &Extensions, _: &mut Self::State) -> Option<usize> {
+        Some(0)
+    }
+
+    fn record(&self, item: &Url, outcome: &Outcome, _: &mut Self::State) {
+        self.0.lock().unwrap().push((item.clone(), *outcome));
+    }
+}
+
+#[tokio::test]
+async fn record_outcome() {
+    // the port of the closed listener refuses the connection
+    let refused = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        Url::parse(&format!("http://127.0.0.1:{}", port)).unwrap()
+    };
+    let hosts = [
+        ("ok-server", serve(200).await),
+        ("not-found-server", serve(404).await),
+        ("unavailable-server", serve(503).await),
+        ("refused-server", refused),
+    ];
+
+    let recorder = Recorder::default();
+    let mut registry = LoadBalancerRegistry::default();
+    for (host, url) in hosts.iter() {
+        let policy = LoadBalancerPolicy::stateful(recorder.clone());
+        registry.add(host, LoadBalancer::new(vec![url.clone()], policy));
+    }
+    let client = ClientBuilder::new(Client::builder().no_proxy().build().unwrap())
+        .with(LoadBalancerMiddleware::new(registry))
+        .build();
+    for (host, _) in hosts.iter() {
+        let _ = client.get(format!("lb://{}/", host)).send().await;
+    }
+
+    let outcomes = recorder
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(url, outcome)| (url.clone(), outcome.success, outcome.status))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        outcomes,
+        vec![
+            (hosts[0].1.clone(), true, Some(StatusCode::OK)),
+            (hosts[1].1.clone(), true, Some(StatusCode::NOT_FOUND)),
+            (
+                hosts[2].1.clone(),
+                false,
+                Some(StatusCode::SERVICE_UNAVAILABLE)
+            ),
+            (hosts[3].1.clone(), false, None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn server_load() {
+    let loaded = serve_load(200, "TEXT cpu_utilization=9, mem_utilization=0.2").await;
+    let idle = serve_load(200, "0.0").await;
+    let mut registry = LoadBalancerRegistry::default();
+    registry.add(
+        "example-server",
+        LoadBalancer::new(
+            vec![loaded.clone(), idle],
+            LoadBalancerPolicy::server_load(LoadBalancerPolicy::Random, Duration::from_secs(60)),
+        ),
+    );
+    let middleware = LoadBalancerMiddleware::new(registry)
+        .load_header(HeaderName::from_static("endpoint-load-metrics"));
+    let client = ClientBuilder::new(Client::builder().no_proxy().build().unwrap())
+        .with(middleware)
+        .build();
+
+    // the loaded element gets about a tenth of the traffic after it reported the load
+    let mut count = 0;
+    for _ in 0..200 {
+        let response = client.get("lb://example-server/").send().await.unwrap();
+        if response.url().port() == loaded.port() {
+            count += 1;
+        }
+    }
+    assert!(count > 0 && count < 60, "{}", count);
+}
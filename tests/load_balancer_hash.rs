@@ -0,0 +1,126 @@
+use http::Extensions;
+use reqwest_lb::{supplier::LoadBalancer, HashKey, LoadBalancerPolicy, LoadBalancerTrait};
+
+const ITEMS: [usize; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+async fn choose<L>(load_balancer: &L, key: u64) -> usize
+where
+    L: LoadBalancerTrait<Element = usize>,
+    L::Error: std::fmt::Debug,
+{
+    let mut extensions = Extensions::new();
+    extensions.insert(HashKey::new(&key));
+    load_balancer
+        .choose(&mut extensions)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn consistent_hash() {
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::consistent_hash(160));
+    for key in 0..100 {
+        let selected = choose(&load_balancer, key).await;
+        assert_eq!(selected, choose(&load_balancer, key).await);
+    }
+
+    // remove the element 0, only the keys on it will be moved
+    let removed = LoadBalancer::new(
+        ITEMS[1..].to_vec(),
+        LoadBalancerPolicy::consistent_hash(160),
+    );
+    for key in 0..100 {
+        let selected = choose(&load_balancer, key).await;
+        if selected != 0 {
+            assert_eq!(selected, choose(&removed, key).await);
+        }
+    }
+}
+
+#[tokio::test]
+async fn maglev() {
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::maglev(65537));
+    let mut counts = [0; ITEMS.len()];
+    for key in 0..1000 {
+        let selected = choose(&load_balancer, key).await;
+        assert_eq!(selected, choose(&load_balancer, key).await);
+        counts[selected] += 1;
+    }
+    assert!(counts.iter().all(|count| *count > 0));
+
+    // remove the element 0, only the keys on it will be moved in most cases
+    let removed = LoadBalancer::new(ITEMS[1..].to_vec(), LoadBalancerPolicy::maglev(65537));
+    let mut moved = 0;
+    for key in 0..1000 {
+        let selected = choose(&load_balancer, key).await;
+        if selected != 0 && selected != choose(&removed, key).await {
+            moved += 1;
+        }
+    }
+    assert!(moved < 100);
+}
+
+#[tokio::test]
+async fn rendezvous() {
+    // the element 0 has no weight, the element 9 has the most weight
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::rendezvous(|i| *i));
+    let mut counts = [0; ITEMS.len()];
+    for key in 0..1000 {
+        let selected = choose(&load_balancer, key).await;
+        assert_eq!(selected, choose(&load_balancer, key).await);
+        counts[selected] += 1;
+    }
+    assert_eq!(counts[0], 0);
+    assert!(counts[9] > counts[1]);
+}
+
+#[tokio::test]
+async fn fallback() {
+    let load_balancer = LoadBalancer::new(
+        ITEMS,
+        LoadBalancerPolicy::fallback([
+            LoadBalancerPolicy::consistent_hash(160),
+            LoadBalancerPolicy::Last,
+        ]),
+    );
+
+    // the request without the key falls through to the next policy
+    let selected = load_balancer.choose(&mut Extensions::new()).await;
+    assert_eq!(selected, Ok(Some(9)));
+
+    let selected = choose(&load_balancer, 7).await;
+    let expect = choose(
+        &LoadBalancer::new(ITEMS, LoadBalancerPolicy::consistent_hash(160)),
+        7,
+    )
+    .await;
+    assert_eq!(selected, expect);
+}
+
+#[tokio::test]
+async fn without_key() {
+    let policies = [
+        LoadBalancerPolicy::consistent_hash(160),
+        LoadBalancerPolicy::maglev(65537),
+        LoadBalancerPolicy::rendezvous(|_: &usize| 1),
+    ];
+    for policy in policies {
+        // the standalone policy chooses a random element without the key
+        let load_balancer = LoadBalancer::new(ITEMS, policy.clone());
+        let selected = load_balancer.choose(&mut Extensions::new()).await;
+        assert!(matches!(selected, Ok(Some(item)) if item < ITEMS.len()));
+
+        // or declines if it's opted in
+        let load_balancer = LoadBalancer::new(ITEMS, policy.decline_without_key());
+        let selected = load_balancer.choose(&mut Extensions::new()).await;
+        assert_eq!(selected, Ok(None));
+    }
+}
+
+#[test]
+fn stable_hash_key() {
+    // the hash is fixed, so the peers built by the different toolchains agree on it
+    assert_eq!(HashKey::new("user-1").0, 11716436280393700400);
+    assert_eq!(HashKey::new(&42u64).0, 11971793068411553624);
+}
@@ -1,5 +1,16 @@
 use http::Extensions;
-use reqwest_lb::{supplier::LoadBalancer, LoadBalancerPolicy, LoadBalancerTrait};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use reqwest_lb::{
+    supplier::LoadBalancer, Aperture, ChooseError, Feedback, HashKey, LoadBalancerPolicy,
+    LoadBalancerPolicyTrait, LoadBalancerTrait, Locality, OutOfRange, Priority, Route, SlowStart,
+    Split, StatefulPolicy, Statistic,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
 
 const ITEMS: [usize; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
 
@@ -23,6 +34,42 @@ async fn round_robin() {
     .await;
 }
 
+#[tokio::test]
+async fn round_robin_concurrent() {
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::RoundRobin);
+    let mut extensions = Extensions::new();
+
+    // the futures are created before any of them is polled
+    let futures = ITEMS
+        .iter()
+        .map(|_| load_balancer.choose(&mut extensions))
+        .collect::<Vec<_>>();
+    let selected = futures::future::join_all(futures).await;
+    assert_eq!(selected, ITEMS.map(|i| Ok(Some(i))));
+}
+
+#[tokio::test]
+async fn round_robin_independent() {
+    let a = LoadBalancer::new(ITEMS, LoadBalancerPolicy::RoundRobin);
+    let b = LoadBalancer::new(ITEMS, LoadBalancerPolicy::RoundRobin);
+
+    // the request pass through two load balancers with the same extensions
+    let mut extensions = Extensions::new();
+    for expect in ITEMS {
+        assert_eq!(a.choose(&mut extensions).await, Ok(Some(expect)));
+        assert_eq!(b.choose(&mut extensions).await, Ok(Some(expect)));
+    }
+}
+
+#[tokio::test]
+async fn round_robin_cursor() {
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::RoundRobin);
+    let mut extensions = Extensions::new();
+    load_balancer.set_cursor(5);
+    assert_eq!(load_balancer.choose(&mut extensions).await, Ok(Some(5)));
+    assert_eq!(load_balancer.cursor(), 6);
+}
+
 #[tokio::test]
 async fn random() {
     choose(LoadBalancerPolicy::Random, |_, selected| {
@@ -55,10 +102,651 @@ async fn weight() {
     .await;
 }
 
+#[tokio::test]
+async fn weight_zero() {
+    // all the elements weigh the same if all the weights are zero
+    let policies = [
+        LoadBalancerPolicy::weight(|_| 0),
+        LoadBalancerPolicy::smooth_weight(|_| 0),
+        LoadBalancerPolicy::weighted_least_requests(|_| 0),
+    ];
+    for policy in policies {
+        let load_balancer = LoadBalancer::new(ITEMS, policy);
+        let mut selected = HashSet::new();
+        for _ in 0..100 {
+            let item = load_balancer.choose(&mut Extensions::new()).await;
+            selected.insert(item.unwrap().unwrap());
+        }
+        assert!(selected.len() > 1);
+    }
+}
+
+#[tokio::test]
+async fn weight_overflow() {
+    // only the last two elements have weight, and the sum of them overflow usize
+    choose(
+        LoadBalancerPolicy::weight(|i| if *i >= 8 { usize::MAX } else { 0 }),
+        |_, selected| selected >= 8,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn smooth_weight() {
+    let load_balancer = LoadBalancer::new(
+        [0usize, 1, 2],
+        LoadBalancerPolicy::smooth_weight(|i| if *i == 0 { 5 } else { 1 }),
+    );
+    let mut extensions = Extensions::new();
+    let mut selected = Vec::new();
+    for _ in 0..14 {
+        selected.push(
+            load_balancer
+                .choose(&mut extensions)
+                .await
+                .unwrap()
+                .unwrap(),
+        );
+    }
+    assert_eq!(selected, [0, 0, 1, 0, 2, 0, 0, 0, 0, 1, 0, 2, 0, 0]);
+}
+
 #[tokio::test]
 async fn dynamic() {
     choose(LoadBalancerPolicy::dynamic(|_, _| 0), |_, selected| {
         selected == 0
     })
     .await;
+
+    // the statistic of the choose is in the extensions
+    let policy = LoadBalancerPolicy::dynamic(|items, extensions| {
+        let statistic = extensions.get::<Statistic>().unwrap();
+        (statistic.cursor() % items.len() as u64) as usize
+    });
+    choose(policy, |expect, selected| expect == selected).await;
+}
+
+#[tokio::test]
+async fn least_requests() {
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::least_requests());
+    let mut extensions = Extensions::new();
+    let mut selected = Vec::new();
+    for _ in ITEMS {
+        // keep all the chosen elements in flight
+        let item = load_balancer
+            .choose(&mut extensions)
+            .await
+            .unwrap()
+            .unwrap();
+        load_balancer.start(&item, &Route::default());
+        selected.push(item);
+    }
+    selected.sort();
+    assert_eq!(selected, ITEMS);
+
+    // finish the element 3, then it has the fewest in flight requests
+    load_balancer.finish(&3, Duration::ZERO, &Route::default());
+    let selected = load_balancer.choose(&mut extensions).await;
+    assert_eq!(selected, Ok(Some(3)));
+}
+
+#[tokio::test]
+async fn weighted_least_requests() {
+    // the element 0 has the weight 4 and the others have the weight 1, the element 9 never
+    let policy = LoadBalancerPolicy::weighted_least_requests(|i: &usize| match i {
+        0 => 4,
+        9 => 0,
+        _ => 1,
+    });
+    let load_balancer = LoadBalancer::new(ITEMS, policy);
+    let mut extensions = Extensions::new();
+    let mut counts = [0; 10];
+    for _ in 0..36 {
+        // keep all the chosen elements in flight
+        let item = load_balancer
+            .choose(&mut extensions)
+            .await
+            .unwrap()
+            .unwrap();
+        load_balancer.start(&item, &Route::default());
+        counts[item] += 1;
+    }
+    assert_eq!(counts, [12, 3, 3, 3, 3, 3, 3, 3, 3, 0]);
+}
+
+#[tokio::test]
+async fn peak_ewma() {
+    let load_balancer = LoadBalancer::new(
+        [0usize, 1],
+        LoadBalancerPolicy::peak_ewma(Duration::from_secs(10)),
+    );
+    let mut extensions = Extensions::new();
+
+    // the element 0 is degraded
+    load_balancer.start(&0, &Route::default());
+    load_balancer.finish(&0, Duration::from_secs(1), &Route::default());
+    load_balancer.start(&1, &Route::default());
+    load_balancer.finish(&1, Duration::from_millis(1), &Route::default());
+    for _ in ITEMS {
+        let selected = load_balancer.choose(&mut extensions).await;
+        assert_eq!(selected, Ok(Some(1)));
+    }
+}
+
+#[tokio::test]
+async fn least_response_time() {
+    let load_balancer = LoadBalancer::new(
+        [0usize, 1, 2],
+        LoadBalancerPolicy::least_response_time(0.9, Duration::from_secs(10), 0.0),
+    );
+    let mut extensions = Extensions::new();
+
+    // the element 2 has no samples, it's taken as the mean rather than the fastest
+    load_balancer.finish(&0, Duration::from_secs(1), &Route::default());
+    load_balancer.finish(&1, Duration::from_millis(1), &Route::default());
+    let selected = load_balancer.choose(&mut extensions).await;
+    assert_eq!(selected, Ok(Some(1)));
+
+    load_balancer.finish(&2, Duration::from_millis(100), &Route::default());
+    for _ in ITEMS {
+        let selected = load_balancer.choose(&mut extensions).await;
+        assert_eq!(selected, Ok(Some(1)));
+    }
+
+    // the element 1 fails fast, the failures are penalized rather than taken as the fastest
+    for _ in 0..2 {
+        Feedback::new(&load_balancer, 1, &extensions).record_failure(None);
+    }
+    let selected = load_balancer.choose(&mut extensions).await;
+    assert_eq!(selected, Ok(Some(2)));
+
+    // the rates not a number are not panics
+    let load_balancer = LoadBalancer::new(
+        [0usize, 1, 2],
+        LoadBalancerPolicy::least_response_time(f64::NAN, Duration::from_secs(10), f64::NAN),
+    );
+    let selected = load_balancer.choose(&mut extensions).await;
+    assert!(matches!(selected, Ok(Some(_))));
+}
+
+#[tokio::test]
+async fn split() {
+    let split = Split::builder(LoadBalancerPolicy::RoundRobin)
+        .group(100, |i: &usize| *i < 8)
+        .group(0, |i: &usize| *i >= 8)
+        .build();
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::split(split.clone()));
+    let mut extensions = Extensions::new();
+    for _ in ITEMS {
+        let selected = load_balancer.choose(&mut extensions).await;
+        assert!(matches!(selected, Ok(Some(selected)) if selected < 8));
+    }
+
+    // move all the traffic to the canary group at runtime
+    split.set_weight(0, 0);
+    split.set_weight(1, 100);
+    for _ in ITEMS {
+        let selected = load_balancer.choose(&mut extensions).await;
+        assert!(matches!(selected, Ok(Some(selected)) if selected >= 8));
+    }
+}
+
+#[tokio::test]
+async fn split_stateful() {
+    // each group keeps the state of the smooth weight, the flips between the groups don't reset it
+    let split = Split::builder(LoadBalancerPolicy::smooth_weight(|i: &usize| {
+        if *i % 2 == 0 {
+            5
+        } else {
+            1
+        }
+    }))
+    .group(50, |i: &usize| *i < 2)
+    .group(50, |i: &usize| *i >= 2)
+    .build();
+    let load_balancer = LoadBalancer::new(vec![0, 1, 2, 3], LoadBalancerPolicy::split(split))
+        .rng(StdRng::seed_from_u64(7));
+    let mut extensions = Extensions::new();
+    let mut counts = [0usize; 4];
+    for _ in 0..1200 {
+        let selected = load_balancer.choose(&mut extensions).await;
+        counts[selected.unwrap().unwrap()] += 1;
+    }
+    for group in [0, 2] {
+        let total = counts[group] + counts[group + 1];
+        assert!(counts[group + 1].abs_diff(total / 6) <= 1, "{:?}", counts);
+    }
+}
+
+#[tokio::test]
+async fn priority() {
+    // the elements less than 5 are primary, and the others are secondary
+    let tier = |i: &usize| if *i < 5 { 0 } else { 1 };
+    let policy = LoadBalancerPolicy::priority(Priority::new(LoadBalancerPolicy::Random, tier));
+    choose(policy, |_, selected| selected < 5).await;
+
+    // the primary tier is unhealthy, fail over to the secondary tier
+    let policy = LoadBalancerPolicy::priority(
+        Priority::new(LoadBalancerPolicy::Random, tier).healthy(|i: &usize| *i >= 5),
+    );
+    choose(policy, |_, selected| selected >= 5).await;
+
+    // the 4/5 primary elements are healthy, 4/5 * 1.4 > 100%, no spillover
+    let policy = LoadBalancerPolicy::priority(
+        Priority::new(LoadBalancerPolicy::Random, tier).healthy(|i: &usize| *i != 0),
+    );
+    choose(policy, |_, selected| (1..5).contains(&selected)).await;
+
+    // the 2/5 primary elements are healthy, the primary tier takes 2/5 * 1.4 = 56% of the traffic,
+    // and the secondary tier takes the rest 44%
+    let policy = LoadBalancerPolicy::priority(
+        Priority::new(LoadBalancerPolicy::Random, tier).healthy(|i: &usize| *i == 0 || *i >= 4),
+    );
+    let load_balancer = LoadBalancer::new(ITEMS, policy).rng(StdRng::seed_from_u64(7));
+    let mut extensions = Extensions::new();
+    let mut primary = 0;
+    for _ in 0..1000 {
+        let selected = load_balancer
+            .choose(&mut extensions)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(selected == 0 || selected >= 4, "{}", selected);
+        if selected < 5 {
+            primary += 1;
+        }
+    }
+    assert!((500..620).contains(&primary), "{}", primary);
+}
+
+#[tokio::test]
+async fn locality() {
+    fn zone(i: &usize) -> &str {
+        if *i < 5 {
+            "a"
+        } else {
+            "b"
+        }
+    }
+    let policy = LoadBalancerPolicy::locality(Locality::new(LoadBalancerPolicy::Random, "a", zone));
+    choose(policy, |_, selected| selected < 5).await;
+
+    // the local zone is unhealthy, spill all the traffic to the other zones
+    let policy = LoadBalancerPolicy::locality(
+        Locality::new(LoadBalancerPolicy::Random, "a", zone).healthy(|i: &usize| *i >= 5),
+    );
+    choose(policy, |_, selected| selected >= 5).await;
+
+    // panic mode: no healthy element at all, the traffic goes to all the elements
+    let policy = LoadBalancerPolicy::locality(
+        Locality::new(LoadBalancerPolicy::Random, "a", zone).healthy(|_: &usize| false),
+    );
+    let load_balancer = LoadBalancer::new(ITEMS, policy);
+    let mut extensions = Extensions::new();
+    let mut selected = HashSet::new();
+    for _ in 0..200 {
+        selected.insert(
+            load_balancer
+                .choose(&mut extensions)
+                .await
+                .unwrap()
+                .unwrap(),
+        );
+    }
+    assert_eq!(selected.len(), ITEMS.len());
+}
+
+#[tokio::test]
+async fn seeded_rng() {
+    async fn sequence(policy: LoadBalancerPolicy<usize>) -> Vec<usize> {
+        let load_balancer = LoadBalancer::new(ITEMS, policy).rng(StdRng::seed_from_u64(7));
+        let mut extensions = Extensions::new();
+        let mut selected = Vec::new();
+        for _ in 0..100 {
+            selected.push(
+                load_balancer
+                    .choose(&mut extensions)
+                    .await
+                    .unwrap()
+                    .unwrap(),
+            );
+        }
+        selected
+    }
+
+    // the same seed gives the identical choice sequence
+    let random = sequence(LoadBalancerPolicy::Random).await;
+    assert_eq!(random, sequence(LoadBalancerPolicy::Random).await);
+    let weight = sequence(LoadBalancerPolicy::weight(|i| *i)).await;
+    assert_eq!(weight, sequence(LoadBalancerPolicy::weight(|i| *i)).await);
+}
+
+#[tokio::test]
+async fn async_policy() {
+    let state = Arc::new(RwLock::new(3usize));
+    let load_balancer = LoadBalancer::with_async_policy(ITEMS, {
+        let state = state.clone();
+        move |items: Vec<usize>, _| {
+            let state = state.clone();
+            async move {
+                let target = *state.read().await;
+                items.into_iter().find(|i| *i == target)
+            }
+        }
+    });
+    let mut extensions = Extensions::new();
+    for _ in ITEMS {
+        let selected = load_balancer.choose(&mut extensions).await;
+        assert_eq!(selected, Ok(Some(3)));
+    }
+
+    // the async policy declines
+    *state.write().await = 10;
+    let selected = load_balancer.choose(&mut extensions).await;
+    assert_eq!(selected, Ok(None));
+}
+
+#[tokio::test]
+async fn out_of_range() {
+    let load_balancer = LoadBalancer::new(
+        ITEMS,
+        LoadBalancerPolicy::dynamic(|items, _| items.len() + 1),
+    );
+    assert_eq!(
+        load_balancer.choose(&mut Extensions::new()).await,
+        Err(ChooseError::OutOfRange(OutOfRange { index: 11, len: 10 }))
+    );
+
+    // the nested policy out of the range is an error too, not a fall through
+    let load_balancer = LoadBalancer::new(
+        ITEMS,
+        LoadBalancerPolicy::fallback([
+            LoadBalancerPolicy::dynamic(|items, _| items.len()),
+            LoadBalancerPolicy::First,
+        ]),
+    );
+    assert_eq!(
+        load_balancer.choose(&mut Extensions::new()).await,
+        Err(ChooseError::OutOfRange(OutOfRange { index: 10, len: 10 }))
+    );
+}
+
+#[tokio::test]
+async fn try_dynamic() {
+    let policy =
+        LoadBalancerPolicy::try_dynamic(|items: &[usize], _| (items.len() > 5).then_some(5));
+    let load_balancer = LoadBalancer::new(ITEMS, policy.clone());
+    assert_eq!(
+        load_balancer.choose(&mut Extensions::new()).await,
+        Ok(Some(5))
+    );
+    let load_balancer = LoadBalancer::new([0, 1, 2], policy);
+    assert_eq!(load_balancer.choose(&mut Extensions::new()).await, Ok(None));
+}
+
+#[tokio::test]
+async fn fallback_hooks() {
+    // count the started requests of the policy
+    #[derive(Clone, Default)]
+    struct Counter(Arc<AtomicUsize>);
+
+    impl StatefulPolicy<usize> for Counter {
+        type State = ();
+
+        fn choose(&self, _: &[usize], _: &Extensions, _: &mut Self::State) -> Option<usize> {
+            Some(0)
+        }
+
+        fn start(&self, _: &usize, _: &mut Self::State) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let (first, second) = (Counter::default(), Counter::default());
+    let load_balancer = LoadBalancer::new(
+        ITEMS,
+        LoadBalancerPolicy::fallback([
+            LoadBalancerPolicy::try_dynamic(|_, extensions| {
+                extensions.get::<HashKey>().map(|key| key.0 as usize)
+            }),
+            LoadBalancerPolicy::stateful(first.clone()),
+            LoadBalancerPolicy::stateful(second.clone()),
+        ]),
+    );
+
+    // both requests chose the element 0 by the different policies, and started in the reverse
+    // order, only the policy which chose the element of the request is notified
+    let mut keyed = Extensions::new();
+    keyed.insert(HashKey(0));
+    let mut requests = Vec::new();
+    for mut extensions in [keyed, Extensions::new()] {
+        let item = load_balancer
+            .choose(&mut extensions)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item, 0);
+        requests.push((item, extensions.get::<Route>().cloned().unwrap()));
+    }
+    for (item, route) in requests.iter().rev() {
+        load_balancer.start(item, route);
+    }
+    assert_eq!(first.0.load(Ordering::SeqCst), 1);
+    assert_eq!(second.0.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn stateful() {
+    // choose the element chosen the fewest times, and track the known elements
+    struct Fewest;
+
+    impl StatefulPolicy<usize> for Fewest {
+        type State = HashMap<usize, u64>;
+
+        fn choose(
+            &self,
+            items: &[usize],
+            _: &Extensions,
+            state: &mut Self::State,
+        ) -> Option<usize> {
+            let index = (0..items.len()).min_by_key(|index| state[&items[*index]])?;
+            *state.get_mut(&items[index]).unwrap() += 1;
+            Some(index)
+        }
+
+        fn added(&self, item: &usize, state: &mut Self::State) {
+            state.insert(*item, 0);
+        }
+
+        fn removed(&self, item: &usize, state: &mut Self::State) {
+            state.remove(item);
+        }
+    }
+
+    choose(LoadBalancerPolicy::stateful(Fewest), |expect, selected| {
+        expect == selected
+    })
+    .await;
+
+    // the removed element will be forgotten, and chosen first when it's added again
+    let policy = LoadBalancerPolicy::stateful(Fewest);
+    let statistic = Statistic::default();
+    let mut extensions = Extensions::new();
+    assert_eq!(
+        policy.choose(&[1, 2], &statistic, &mut extensions),
+        Ok(Some(0))
+    );
+    assert_eq!(
+        policy.choose(&[2], &statistic, &mut extensions),
+        Ok(Some(0))
+    );
+    assert_eq!(
+        policy.choose(&[2, 1], &statistic, &mut extensions),
+        Ok(Some(1))
+    );
+}
+
+#[tokio::test]
+async fn aperture() {
+    let items = (0..100).collect::<Vec<usize>>();
+    let mut extensions = Extensions::new();
+
+    // each client talks to a small window, and the windows cover all the elements
+    let mut covered = HashSet::new();
+    for index in 0..20 {
+        let policy = LoadBalancerPolicy::aperture(Aperture::new(index, 20).min_aperture(3));
+        let load_balancer = LoadBalancer::new(items.clone(), policy);
+        let mut chosen = HashSet::new();
+        for _ in 0..200 {
+            chosen.insert(
+                load_balancer
+                    .choose(&mut extensions)
+                    .await
+                    .unwrap()
+                    .unwrap(),
+            );
+        }
+        assert!(chosen.len() <= 6);
+        covered.extend(chosen);
+    }
+    assert_eq!(covered.len(), items.len());
+
+    // the window widens when the requests keep in flight
+    let policy = LoadBalancerPolicy::aperture(Aperture::new(0, 20).min_aperture(3));
+    let load_balancer = LoadBalancer::new(items.clone(), policy);
+    let mut chosen = HashSet::new();
+    for _ in 0..200 {
+        let element = load_balancer
+            .choose(&mut extensions)
+            .await
+            .unwrap()
+            .unwrap();
+        load_balancer.start(&element, &Route::default());
+        chosen.insert(element);
+    }
+    assert!(chosen.len() > 6);
+}
+
+#[tokio::test]
+async fn slow_start() {
+    fn count_new(policy: &LoadBalancerPolicy<usize>) -> usize {
+        let statistic = Statistic::default();
+        let mut extensions = Extensions::new();
+        let items = (0..11).collect::<Vec<_>>();
+        (0..1100)
+            .filter(|_| policy.choose(&items, &statistic, &mut extensions) == Ok(Some(10)))
+            .count()
+    }
+
+    let slow_start = SlowStart::new(LoadBalancerPolicy::Random, Duration::from_secs(60));
+    let policy = LoadBalancerPolicy::slow_start(slow_start);
+    let statistic = Statistic::default();
+    let mut extensions = Extensions::new();
+
+    // the initial elements are warmed up, and the new element 10 starts from the min factor
+    assert!(policy
+        .choose(&ITEMS, &statistic, &mut extensions)
+        .unwrap()
+        .is_some());
+    assert!(count_new(&policy) < 50);
+
+    // the new element gets the full share after the window
+    let added = Instant::now() - Duration::from_secs(120);
+    let slow_start = SlowStart::new(LoadBalancerPolicy::Random, Duration::from_secs(60))
+        .since(move |item: &usize| (*item == 10).then_some(added));
+    assert!(count_new(&LoadBalancerPolicy::slow_start(slow_start)) > 50);
+}
+
+#[tokio::test]
+async fn slow_start_stateful() {
+    // the element 9 was added at the half of the window, it takes the half weight of the others
+    let added = Instant::now() - Duration::from_secs(30);
+    let slow_start = SlowStart::new(
+        LoadBalancerPolicy::smooth_weight(|_| 1),
+        Duration::from_secs(60),
+    )
+    .since(move |item: &usize| (*item == 9).then_some(added));
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::slow_start(slow_start));
+    let mut extensions = Extensions::new();
+    let mut counts = HashMap::<usize, usize>::new();
+    for _ in 0..19 {
+        let selected = load_balancer.choose(&mut extensions).await;
+        *counts.entry(selected.unwrap().unwrap()).or_default() += 1;
+    }
+    assert_eq!(counts[&9], 1);
+    assert!((0..9).all(|item| counts[&item] == 2));
+}
+
+#[tokio::test]
+async fn slow_start_round_robin() {
+    // the element 9 was just added, it takes the min factor of the round robin share
+    let added = Instant::now();
+    let slow_start = SlowStart::new(LoadBalancerPolicy::RoundRobin, Duration::from_secs(60))
+        .since(move |item: &usize| (*item == 9).then_some(added));
+    let load_balancer = LoadBalancer::new(ITEMS, LoadBalancerPolicy::slow_start(slow_start));
+    let mut extensions = Extensions::new();
+    let mut counts = HashMap::<usize, usize>::new();
+    for _ in 0..910 {
+        let selected = load_balancer.choose(&mut extensions).await;
+        *counts.entry(selected.unwrap().unwrap()).or_default() += 1;
+    }
+    assert!((5..=15).contains(&counts[&9]));
+    assert!((0..9).all(|item| (95..=105).contains(&counts[&item])));
+
+    // the new element with the zero factor is still chosen when it's the only one
+    let slow_start = SlowStart::new(LoadBalancerPolicy::RoundRobin, Duration::from_secs(60))
+        .min_factor(0.0)
+        .since(move |_: &usize| Some(added));
+    let load_balancer = LoadBalancer::new([1], LoadBalancerPolicy::slow_start(slow_start));
+    assert_eq!(load_balancer.choose(&mut extensions).await, Ok(Some(1)));
+}
+
+#[tokio::test]
+async fn slow_start_fork() {
+    // the load balancers with the different elements don't ramp up the elements of each other
+    let slow_start = SlowStart::new(LoadBalancerPolicy::RoundRobin, Duration::from_secs(60));
+    let policy = LoadBalancerPolicy::slow_start(slow_start);
+    let first = LoadBalancer::new((0..10).collect::<Vec<_>>(), policy.clone());
+    let second = LoadBalancer::new((10..20).collect::<Vec<_>>(), policy);
+    let mut extensions = Extensions::new();
+    assert!(first.choose(&mut extensions).await.unwrap().is_some());
+    assert!(second.choose(&mut extensions).await.unwrap().is_some());
+    let mut counts = HashMap::<usize, usize>::new();
+    for _ in 0..100 {
+        let selected = second.choose(&mut extensions).await;
+        *counts.entry(selected.unwrap().unwrap()).or_default() += 1;
+    }
+    assert!((10..20).all(|item| counts[&item] == 10));
+}
+
+#[tokio::test]
+async fn offset_round_robin() {
+    let mut starts = HashSet::new();
+    for seed in 0..20 {
+        let policy = LoadBalancerPolicy::offset_round_robin();
+        let load_balancer = LoadBalancer::new(ITEMS, policy).rng(StdRng::seed_from_u64(seed));
+        let mut extensions = Extensions::new();
+        let start = load_balancer
+            .choose(&mut extensions)
+            .await
+            .unwrap()
+            .unwrap();
+        // round robin from the offset
+        for step in 1..20 {
+            let selected = load_balancer.choose(&mut extensions).await;
+            assert_eq!(selected, Ok(Some((start + step) % ITEMS.len())));
+        }
+        starts.insert(start);
+    }
+
+    // the load balancers don't start from the same element
+    assert!(starts.len() > 1);
+}
+
+#[test]
+fn debug() {
+    let policy = LoadBalancerPolicy::<usize>::least_requests();
+    assert_eq!(format!("{:?}", policy), "Custom(LeastRequests)");
+    let policy = LoadBalancerPolicy::<usize>::rendezvous(|_| 1).decline_without_key();
+    assert_eq!(format!("{:?}", policy), "Custom(Rendezvous)");
 }
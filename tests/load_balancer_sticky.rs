@@ -0,0 +1,147 @@
+use http::header::{COOKIE, SET_COOKIE};
+use reqwest::{Client, Url};
+use reqwest_lb::supplier::LoadBalancer;
+use reqwest_lb::{
+    Affinity, LoadBalancerMiddleware, LoadBalancerPolicy, LoadBalancerRegistry, StickySession,
+};
+use reqwest_middleware::ClientBuilder;
+use std::collections::HashSet;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+///
+/// start a http server which responds its port
+///
+async fn serve() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await;
+            let body = port.to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn sticky_session() {
+    let mut urls = Vec::new();
+    for _ in 0..3 {
+        let port = serve().await;
+        urls.push(Url::parse(&format!("http://127.0.0.1:{}", port)).unwrap());
+    }
+    let mut registry = LoadBalancerRegistry::default();
+    registry.add(
+        "example-server",
+        LoadBalancer::new(urls, LoadBalancerPolicy::RoundRobin),
+    );
+    let middleware = LoadBalancerMiddleware::new(registry).sticky("LB_AFFINITY");
+    let client = ClientBuilder::new(Client::builder().no_proxy().build().unwrap())
+        .with(middleware)
+        .build();
+
+    // the first response records the element in the cookie
+    let response = client.get("lb://example-server/").send().await.unwrap();
+    let cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+    let cookie = cookie.split(';').next().unwrap().to_string();
+    let port = response.text().await.unwrap();
+
+    // the later requests go back to the same element
+    for _ in 0..5 {
+        let response = client
+            .get("lb://example-server/")
+            .header(COOKIE, &cookie)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.headers().get(SET_COOKIE).is_none());
+        assert_eq!(response.text().await.unwrap(), port);
+    }
+
+    // the unknown element falls back to the policy
+    let response = client
+        .get("lb://example-server/")
+        .header(COOKIE, "LB_AFFINITY=unknown")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.headers().get(SET_COOKIE).is_some());
+}
+
+#[tokio::test]
+async fn sticky_session_without_cookie() {
+    let mut urls = Vec::new();
+    for _ in 0..3 {
+        let port = serve().await;
+        urls.push(Url::parse(&format!("http://127.0.0.1:{}", port)).unwrap());
+    }
+    let mut registry = LoadBalancerRegistry::default();
+    registry.add(
+        "example-server",
+        LoadBalancer::new(urls.clone(), LoadBalancerPolicy::RoundRobin),
+    );
+    let middleware = LoadBalancerMiddleware::new(registry).sticky("LB_AFFINITY");
+    let client = ClientBuilder::new(Client::builder().no_proxy().build().unwrap())
+        .with(middleware)
+        .build();
+
+    // the requests without the cookie or the session key are not pinned
+    let mut ports = HashSet::new();
+    for _ in 0..3 {
+        let response = client.get("lb://example-server/").send().await.unwrap();
+        ports.insert(response.text().await.unwrap());
+    }
+    assert_eq!(ports.len(), 3);
+
+    // the requests of the session key go back to the same element, the other keys are not
+    // pinned by it
+    let session = || StickySession::new("user-1");
+    let response = client
+        .get("lb://example-server/")
+        .with_extension(session())
+        .send()
+        .await
+        .unwrap();
+    let port = response.text().await.unwrap();
+    let mut others = HashSet::new();
+    for user in 0..3 {
+        let response = client
+            .get("lb://example-server/")
+            .with_extension(session())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), port);
+        let response = client
+            .get("lb://example-server/")
+            .with_extension(StickySession::new(format!("other-{}", user)))
+            .send()
+            .await
+            .unwrap();
+        others.insert(response.text().await.unwrap());
+    }
+    assert!(others.len() > 1);
+
+    // the affinity of the user takes precedence over the session
+    let other = urls
+        .iter()
+        .find(|url| url.port().unwrap().to_string() != port)
+        .unwrap()
+        .clone();
+    let response = client
+        .get("lb://example-server/")
+        .with_extension(session())
+        .with_extension(Affinity::new(move |url: &Url| *url == other))
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(response.text().await.unwrap(), port);
+}
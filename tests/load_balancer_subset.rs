@@ -0,0 +1,82 @@
+use futures::channel::mpsc::unbounded;
+use http::Extensions;
+use reqwest_lb::discovery::Change;
+use reqwest_lb::supplier::{
+    DiscoverySupplier, Labeled, LoadBalancer, Selector, SubsetSupplier, Supplier,
+};
+use reqwest_lb::{LoadBalancerPolicy, LoadBalancerTrait};
+use std::convert::Infallible;
+use std::sync::atomic::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    port: u16,
+    version: &'static str,
+    region: &'static str,
+}
+
+impl Labeled for Node {
+    fn label(&self, key: &str) -> Option<&str> {
+        match key {
+            "version" => Some(self.version),
+            "region" => Some(self.region),
+            _ => None,
+        }
+    }
+}
+
+async fn ports<L: LoadBalancerTrait<Element = Node, Error = Infallible>>(
+    load_balancer: &L,
+) -> Vec<u16> {
+    let mut extensions = Extensions::new();
+    let mut ports = Vec::new();
+    for _ in 0..4 {
+        let node = load_balancer.choose(&mut extensions).await.unwrap();
+        ports.extend(node.map(|node| node.port));
+    }
+    ports.sort();
+    ports.dedup();
+    ports
+}
+
+#[tokio::test]
+async fn label_selector() {
+    let (tx, rx) = unbounded::<Result<Change<u16, Node>, Infallible>>();
+    let insert = |port, version, region| {
+        let node = Node {
+            port,
+            version,
+            region,
+        };
+        tx.unbounded_send(Ok(Change::Insert(port, node))).unwrap();
+    };
+    insert(3000, "v1", "us");
+    insert(3001, "v2", "us");
+    insert(3002, "v2", "eu");
+    tx.unbounded_send(Ok(Change::Initialized)).unwrap();
+
+    // the subsets share one discovery supplier
+    let supplier = DiscoverySupplier::new(rx);
+    let generation = supplier.generation().unwrap();
+    let v1 = SubsetSupplier::new(supplier.clone(), "version=v1".parse().unwrap());
+    let v2 = SubsetSupplier::new(supplier, "version=v2,region!=eu".parse().unwrap());
+    let v1 = LoadBalancer::new(v1, LoadBalancerPolicy::RoundRobin);
+    let v2 = LoadBalancer::new(v2, LoadBalancerPolicy::RoundRobin);
+    assert_eq!(ports(&v1).await, [3000]);
+    assert_eq!(ports(&v2).await, [3001]);
+
+    // the subset follows the discovery changes
+    insert(3003, "v2", "us");
+    tx.unbounded_send(Ok(Change::Remove(3000))).unwrap();
+    while generation.load(Ordering::SeqCst) < 5 {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(ports(&v1).await, Vec::<u16>::new());
+    assert_eq!(ports(&v2).await, [3001, 3003]);
+}
+
+#[test]
+fn invalid_selector() {
+    assert!("version=v1,,!region".parse::<Selector>().is_ok());
+    assert!("version=v1,=v2".parse::<Selector>().is_err());
+}